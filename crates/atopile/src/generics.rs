@@ -0,0 +1,78 @@
+use schematics::{
+    part::Part,
+    standard_library::{capacitor, inductor, led, resistor},
+    value::{unit_for_key, ComponentValue},
+};
+
+/// A standard library part which Atopile's generics library provides, so
+/// that it can be used with `new Resistor` rather than being written out as
+/// a component of its own.
+pub struct AtopileGeneric {
+    pub part: &'static str,
+
+    /// The file in the generics library that defines the part.
+    pub file: &'static str,
+
+    /// The signals of the part, which must match for a part to be treated as
+    /// the generic.
+    signals: &'static [&'static str],
+}
+
+const GENERICS: &[AtopileGeneric] = &[
+    AtopileGeneric {
+        part: resistor::NAME,
+        file: "generics/resistors.ato",
+        signals: &["p1", "p2"],
+    },
+    AtopileGeneric {
+        part: capacitor::NAME,
+        file: "generics/capacitors.ato",
+        signals: &["p1", "p2"],
+    },
+    AtopileGeneric {
+        part: inductor::NAME,
+        file: "generics/inductors.ato",
+        signals: &["p1", "p2"],
+    },
+    AtopileGeneric {
+        part: led::NAME,
+        file: "generics/leds.ato",
+        signals: &["anode", "cathode"],
+    },
+];
+
+/// Returns the generic that `part` can be replaced with, if any. Designs may
+/// define their own parts with the same name as a generic (e.g. `LED`), so
+/// the signals have to match too.
+pub fn find_generic(part: &Part) -> Option<&'static AtopileGeneric> {
+    let generic = GENERICS.iter().find(|g| g.part == part.name)?;
+
+    let mut signals: Vec<String> = part
+        .ports_by_terminal_identifier
+        .values()
+        .map(|p| p.as_deref().signal.clone())
+        .collect();
+    signals.sort();
+    signals.dedup();
+    (signals == generic.signals).then_some(generic)
+}
+
+/// Format a value stored under `key`, such as a `resistance` of `4k7`, as an
+/// Atopile quantity (`4.7kohm`). Returns `None` for values which can't be
+/// parsed.
+pub fn quantity(value: &str, key: &str) -> Option<String> {
+    let unit = unit_for_key(key)?;
+    ComponentValue::parse(value, unit)
+        .ok()
+        .map(|v| v.to_string())
+}
+
+#[test]
+fn test_quantity() {
+    assert_eq!(quantity("10k", "resistance"), Some("10kohm".to_string()));
+    assert_eq!(quantity("100nF", "capacitance"), Some("100nF".to_string()));
+    assert_eq!(quantity("4.7 µH", "inductance"), Some("4.7uH".to_string()));
+    assert_eq!(quantity("4k7", "resistance"), Some("4.7kohm".to_string()));
+    assert_eq!(quantity("DNP", "resistance"), None);
+    assert_eq!(quantity("10k", "value"), None);
+}
@@ -0,0 +1,458 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use natord::compare;
+use schematics::{
+    component::{ComponentRef, KICAD_SHEET_FILE_KEY, KICAD_TSTAMP_KEY},
+    net::NetRef,
+    Schematic,
+};
+
+/// The index of the root sheet.
+pub const ROOT: usize = 0;
+
+/// A sheet of the schematic, at one place in the hierarchy. Sheets that are
+/// used more than once have an instance for every use.
+pub struct SheetInstance {
+    /// The name of the sheet in its parent, e.g. `LDO`. Empty for the root.
+    pub name: String,
+
+    pub parent: Option<usize>,
+
+    /// The child instances, in natural order of name.
+    pub children: Vec<usize>,
+
+    /// The components placed directly on the sheet, in natural order of
+    /// name.
+    pub components: Vec<ComponentRef>,
+
+    /// The file the sheet was loaded from, if the importer recorded it.
+    file: Option<String>,
+
+    /// The module the instance is written out as. `None` for the root.
+    pub module: Option<usize>,
+}
+
+/// A sheet which is written out once as a module, and instantiated for each
+/// of its instances.
+pub struct SheetModule {
+    /// The instance whose contents are written out as the module.
+    pub representative: usize,
+
+    pub instances: Vec<usize>,
+}
+
+/// What the contents of an instance must match for it to share a module with
+/// another instance.
+#[derive(PartialEq)]
+struct Signature {
+    file: String,
+
+    /// The part and key (see `component_keys`) of each component.
+    components: Vec<(String, String)>,
+
+    /// The name and module of each child instance.
+    children: Vec<(String, Option<usize>)>,
+}
+
+/// A signal in the module of an instance, connecting pins that are connected
+/// in every instance of the module.
+pub struct ModuleSignal {
+    /// The net the signal is part of in the instance, which it's named after.
+    pub net: NetRef,
+
+    /// Whether the signal is connected outside the module in any of its
+    /// instances, so is part of the module's interface.
+    pub interface: bool,
+
+    /// The pins on the signal, as the local name of the component and the
+    /// signal of its port.
+    pub ports: Vec<(String, String)>,
+
+    /// The interface signals of child instances on the signal, as the child
+    /// instance and the index of the signal in its module.
+    pub children: Vec<(usize, usize)>,
+}
+
+/// A pin below an instance: the names of the instances leading to its
+/// component, the local name of the component, and the signal of the port.
+type PinPath = Vec<String>;
+
+/// The sheet hierarchy of a schematic, recovered from the sheet paths
+/// recorded on its components.
+pub struct SheetTree {
+    pub instances: Vec<SheetInstance>,
+    pub modules: Vec<SheetModule>,
+
+    /// The instance each component was placed on.
+    instance_of: HashMap<ComponentRef, usize>,
+
+    /// The name of each component in the module that defines it, which is
+    /// its name in the module's representative instance.
+    local_names: HashMap<ComponentRef, String>,
+}
+
+/// Keys which identify the components of an instance across every instance
+/// of the same sheet: the UUID of the component's symbol if it's known, or
+/// else its position among the components of the same part.
+fn component_keys(components: &[ComponentRef]) -> BTreeMap<(String, String), ComponentRef> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    components
+        .iter()
+        .map(|component| {
+            let c = component.as_deref();
+            let part = c.part.as_deref().name.clone();
+            let key = match c.metadata.get(KICAD_TSTAMP_KEY) {
+                Some(tstamp) => tstamp.clone(),
+                None => {
+                    let count = counts.entry(part.clone()).or_default();
+                    *count += 1;
+                    format!("#{}", count)
+                }
+            };
+            ((part, key), component.clone())
+        })
+        .collect()
+}
+
+impl SheetTree {
+    pub fn from_schematic(schematic: &Schematic) -> Self {
+        let mut tree = SheetTree {
+            instances: vec![SheetInstance {
+                name: String::new(),
+                parent: None,
+                children: vec![],
+                components: vec![],
+                file: None,
+                module: None,
+            }],
+            modules: vec![],
+            instance_of: HashMap::new(),
+            local_names: HashMap::new(),
+        };
+
+        let mut components: Vec<&ComponentRef> = schematic.components_iter().collect();
+        components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+        let mut instances_by_key: HashMap<String, usize> = HashMap::new();
+        for component in components {
+            let (names, tstamps, file) = {
+                let c = component.as_deref();
+                (
+                    c.sheet_path(),
+                    c.sheet_tstamps(),
+                    c.metadata.get(KICAD_SHEET_FILE_KEY).cloned(),
+                )
+            };
+
+            // Sheets are told apart by the UUIDs of their sheet symbols when
+            // they're known, since names are only unique within a sheet.
+            let keys = match tstamps.len() == names.len() {
+                true => &tstamps,
+                false => &names,
+            };
+            let mut index = ROOT;
+            let mut key = String::new();
+            for (name, segment) in names.iter().zip(keys.iter()) {
+                key = format!("{}/{}", key, segment);
+                index = match instances_by_key.get(&key) {
+                    Some(child) => *child,
+                    None => {
+                        tree.instances.push(SheetInstance {
+                            name: name.clone(),
+                            parent: Some(index),
+                            children: vec![],
+                            components: vec![],
+                            file: None,
+                            module: None,
+                        });
+                        let child = tree.instances.len() - 1;
+                        tree.instances[index].children.push(child);
+                        instances_by_key.insert(key.clone(), child);
+                        child
+                    }
+                };
+            }
+
+            if index != ROOT && file.is_some() {
+                tree.instances[index].file = file;
+            }
+            tree.instances[index].components.push(component.clone());
+            tree.instance_of.insert(component.clone(), index);
+        }
+
+        for index in 0..tree.instances.len() {
+            let mut children = std::mem::take(&mut tree.instances[index].children);
+            children.sort_by(|a, b| compare(&tree.instances[*a].name, &tree.instances[*b].name));
+            tree.instances[index].children = children;
+        }
+
+        tree.assign_modules(ROOT);
+        tree
+    }
+
+    fn signature(&self, index: usize) -> Option<Signature> {
+        let instance = &self.instances[index];
+        Some(Signature {
+            // Sheets of unknown files can't be shared.
+            file: instance.file.clone()?,
+            components: component_keys(&instance.components).into_keys().collect(),
+            children: instance
+                .children
+                .iter()
+                .map(|c| (self.instances[*c].name.clone(), self.instances[*c].module))
+                .collect(),
+        })
+    }
+
+    /// Group the instances into modules, deepest first, so that instances of
+    /// the same sheet share a module as long as their contents match.
+    fn assign_modules(&mut self, index: usize) {
+        for child in self.instances[index].children.clone() {
+            self.assign_modules(child);
+        }
+        if index == ROOT {
+            return;
+        }
+
+        let signature = self.signature(index);
+        let existing = signature.as_ref().and_then(|signature| {
+            self.modules
+                .iter()
+                .position(|m| self.signature(m.representative).as_ref() == Some(signature))
+        });
+
+        let representative = match existing {
+            Some(module) => {
+                self.modules[module].instances.push(index);
+                self.instances[index].module = Some(module);
+                self.modules[module].representative
+            }
+            None => {
+                self.modules.push(SheetModule {
+                    representative: index,
+                    instances: vec![index],
+                });
+                self.instances[index].module = Some(self.modules.len() - 1);
+                index
+            }
+        };
+
+        let local_components = component_keys(&self.instances[representative].components);
+        for (key, component) in component_keys(&self.instances[index].components) {
+            let local_name = local_components[&key].as_deref().name.clone();
+            self.local_names.insert(component, local_name);
+        }
+    }
+
+    /// The name of the module's sheet, which the module is named after: the
+    /// name of the sheet if it's only used once, or else of its file.
+    pub fn module_name(&self, module: &SheetModule) -> String {
+        let instance = &self.instances[module.representative];
+        let file_stem = instance
+            .file
+            .as_ref()
+            .and_then(|f| Path::new(f).file_stem())
+            .map(|s| s.to_string_lossy().to_string());
+        match (module.instances.len(), file_stem) {
+            (1, _) | (_, None) => instance.name.clone(),
+            (_, Some(stem)) => {
+                let mut chars = stem.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().collect::<String>() + chars.as_str())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Whether the contents of an instance are written out, rather than
+    /// being covered by another instance of the same module.
+    pub fn is_written(&self, index: usize) -> bool {
+        match self.instances[index].module {
+            Some(module) => self.modules[module].representative == index,
+            None => true,
+        }
+    }
+
+    pub fn instance_of(&self, component: &ComponentRef) -> usize {
+        self.instance_of[component]
+    }
+
+    pub fn local_name(&self, component: &ComponentRef) -> String {
+        self.local_names
+            .get(component)
+            .cloned()
+            .unwrap_or(component.as_deref().name.clone())
+    }
+
+    /// The instances leading from the root to `index`, not including the
+    /// root.
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![];
+        let mut current = index;
+        while let Some(parent) = self.instances[current].parent {
+            path.push(current);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The instances leading from `from` down to `to`, not including `from`.
+    pub fn path(&self, from: usize, to: usize) -> Vec<usize> {
+        let depth = self.ancestors(from).len();
+        self.ancestors(to).split_off(depth)
+    }
+
+    pub fn common_ancestor(&self, a: usize, b: usize) -> usize {
+        self.ancestors(a)
+            .into_iter()
+            .zip(self.ancestors(b))
+            .take_while(|(a, b)| a == b)
+            .last()
+            .map(|(a, _)| a)
+            .unwrap_or(ROOT)
+    }
+
+    /// Whether `a` is an ancestor of `b`, other than `b` itself.
+    fn is_ancestor(&self, a: usize, b: usize) -> bool {
+        a != b && (a == ROOT || self.ancestors(b).contains(&a))
+    }
+
+    /// The signals of every instance whose contents are written out, keyed
+    /// by the instance.
+    pub fn module_signals(&self, schematic: &Schematic) -> HashMap<usize, Vec<ModuleSignal>> {
+        let mut nets: Vec<&NetRef> = schematic.nets_iter().collect();
+        nets.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+        // The net of every pin below each instance, and the instance that
+        // contains all of each net's pins.
+        let mut pin_nets: Vec<BTreeMap<PinPath, usize>> =
+            vec![BTreeMap::new(); self.instances.len()];
+        let mut owners = vec![];
+        for (n, net) in nets.iter().enumerate() {
+            let mut owner = None;
+            for (component, port) in net.as_deref().connections.iter() {
+                let instance = self.instance_of(component);
+                owner = Some(owner.map_or(instance, |o| self.common_ancestor(o, instance)));
+                for above in std::iter::once(ROOT).chain(self.ancestors(instance)) {
+                    let mut pin: PinPath = self
+                        .path(above, instance)
+                        .into_iter()
+                        .map(|i| self.instances[i].name.clone())
+                        .collect();
+                    pin.push(self.local_name(component));
+                    pin.push(port.as_deref().signal.clone());
+                    pin_nets[above].insert(pin, n);
+                }
+            }
+            owners.push(owner.unwrap_or(ROOT));
+        }
+
+        let mut signals: HashMap<usize, Vec<ModuleSignal>> = HashMap::new();
+        let mut signal_of_pin: HashMap<usize, HashMap<PinPath, usize>> = HashMap::new();
+        for index in self.written_instances() {
+            let instances = match self.instances[index].module {
+                Some(module) => self.modules[module].instances.clone(),
+                None => vec![index],
+            };
+
+            // Pins share a signal if they're on the same net in every
+            // instance of the module.
+            let mut groups: BTreeMap<Vec<Option<usize>>, Vec<PinPath>> = BTreeMap::new();
+            for instance in instances.iter() {
+                for pin in pin_nets[*instance].keys() {
+                    let key = instances
+                        .iter()
+                        .map(|i| pin_nets[*i].get(pin).copied())
+                        .collect();
+                    let group = groups.entry(key).or_default();
+                    if !group.contains(pin) {
+                        group.push(pin.clone());
+                    }
+                }
+            }
+
+            let mut module_signals = vec![];
+            let mut module_signal_of_pin = HashMap::new();
+            for (key, pins) in groups {
+                let interface = key
+                    .iter()
+                    .zip(instances.iter())
+                    .any(|(n, i)| n.is_some_and(|n| self.is_ancestor(owners[n], *i)));
+                let mut signal = ModuleSignal {
+                    net: nets[key.iter().flatten().next().copied().unwrap()].clone(),
+                    interface,
+                    ports: vec![],
+                    children: vec![],
+                };
+                for pin in pins.iter() {
+                    match pin.as_slice() {
+                        [component, port] => signal.ports.push((component.clone(), port.clone())),
+                        [child_name, rest @ ..] => {
+                            let child = self.instances[index]
+                                .children
+                                .iter()
+                                .copied()
+                                .find(|c| &self.instances[*c].name == child_name)
+                                .expect("child instance not found");
+                            let representative = self.representative(child);
+                            // Signals inside the child aren't connected here.
+                            let Some(child_signal) = signal_of_pin[&representative].get(rest)
+                            else {
+                                continue;
+                            };
+                            let child_signal = *child_signal;
+                            let is_interface = signals[&representative][child_signal].interface;
+                            if is_interface && !signal.children.contains(&(child, child_signal)) {
+                                signal.children.push((child, child_signal));
+                            }
+                        }
+                        _ => unreachable!("pin paths have a component and a signal"),
+                    }
+                }
+
+                // Signals inside a single child are written in the child.
+                if !interface && signal.ports.is_empty() && signal.children.len() < 2 {
+                    continue;
+                }
+                for pin in pins {
+                    module_signal_of_pin.insert(pin, module_signals.len());
+                }
+                module_signals.push(signal);
+            }
+
+            signals.insert(index, module_signals);
+            signal_of_pin.insert(index, module_signal_of_pin);
+        }
+        signals
+    }
+
+    /// The instance whose contents are written out for `index`.
+    pub fn representative(&self, index: usize) -> usize {
+        match self.instances[index].module {
+            Some(module) => self.modules[module].representative,
+            None => index,
+        }
+    }
+
+    /// The instances whose contents are written out, children before their
+    /// parents.
+    pub fn written_instances(&self) -> Vec<usize> {
+        let mut written = vec![];
+        self.postorder(ROOT, &mut written);
+        written
+    }
+
+    fn postorder(&self, index: usize, written: &mut Vec<usize>) {
+        for child in self.instances[index].children.iter() {
+            self.postorder(*child, written);
+        }
+        if self.is_written(index) {
+            written.push(index);
+        }
+    }
+}
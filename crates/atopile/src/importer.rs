@@ -0,0 +1,784 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use natord::compare;
+use schematics::{
+    component::{ComponentBuilder, KICAD_SHEET_NAME_KEY, KICAD_SHEET_PATH_KEY},
+    net::NetBuilder,
+    net_inference::NetTypeOverrides,
+    part::{PartBuilder, PinType},
+    value::normalize,
+    Schematic,
+};
+use serde::Deserialize;
+
+use crate::{
+    parser::{self, AtoBlock, AtoBlockKind, AtoEndpoint, AtoFile, AtoStatementKind},
+    AtopileError, PIN_TYPE_COMMENT,
+};
+
+/// The pins declared by a statement, e.g. `pin 1` or `signal a ~ pin 1`.
+fn declared_pins(kind: &AtoStatementKind) -> Vec<&str> {
+    let endpoints = match kind {
+        AtoStatementKind::Pin(pin) => return vec![pin.as_str()],
+        AtoStatementKind::Connect(left, right) => [left, right],
+        _ => return vec![],
+    };
+    endpoints
+        .into_iter()
+        .filter_map(|e| match e {
+            AtoEndpoint::Pin(pin) => Some(pin.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Instantiating modules deeper than this is assumed to be recursive.
+const MAX_MODULE_DEPTH: usize = 32;
+
+#[derive(Deserialize)]
+struct AtoConfig {
+    #[serde(default)]
+    builds: BTreeMap<String, AtoBuildConfig>,
+}
+
+#[derive(Deserialize)]
+struct AtoBuildConfig {
+    entry: String,
+}
+
+/// Maps attributes assigned in Atopile to the metadata keys used by the KiCad
+/// importer, so that the two can be compared.
+fn metadata_key(attribute: &str) -> String {
+    match attribute {
+        "mpn" => "MPN".to_string(),
+        "footprint" => "Footprint".to_string(),
+        "value" => "Value".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// The sheet path of a component at an instance path, made of the module
+/// instances enclosing it, e.g. `/power/ldo/` for `power.ldo.u1`.
+fn sheet_path(path: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((modules, _)) => format!("/{}/", modules.replace('.', "/")),
+        None => "/".to_string(),
+    }
+}
+
+/// A block, along with the file it was defined in, so that the symbols it
+/// uses can be resolved relative to that file.
+struct ResolvedBlock {
+    file: PathBuf,
+    block: AtoBlock,
+}
+
+/// A component instance found while elaborating the entry module.
+struct ComponentInstance {
+    /// The instance path, e.g. `power.r1`.
+    path: String,
+    part_name: String,
+    designator: Option<String>,
+    sheet_name: Option<String>,
+
+    /// The pins of the component, mapped to the signal they're connected to
+    /// inside the component.
+    ports: Vec<(String, String)>,
+
+    /// The electrical types of pins, recorded as `# pin_type: ...` comments
+    /// on the line declaring the pin.
+    pin_types: BTreeMap<String, PinType>,
+
+    /// Attributes assigned inside the component block.
+    part_metadata: BTreeMap<String, String>,
+
+    /// Attributes assigned to this instance from the enclosing module.
+    metadata: BTreeMap<String, String>,
+}
+
+/// A signal declared in a module or interface, which may be used to name the
+/// net it belongs to.
+struct SignalDeclaration {
+    node: usize,
+
+    /// The name of the signal relative to the module that declares it.
+    name: String,
+
+    /// The full instance path of the signal.
+    path: String,
+
+    /// The depth of the declaring module, where the entry module is 0.
+    depth: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InstanceKind {
+    Component(usize),
+    Module,
+    Interface,
+}
+
+struct Instance {
+    kind: InstanceKind,
+
+    /// The names of the signals declared directly in this instance.
+    signals: Vec<String>,
+}
+
+struct Elaborator {
+    /// Directories to search for imported files, after the importing file's
+    /// own directory.
+    search_paths: Vec<PathBuf>,
+    files: HashMap<PathBuf, Rc<AtoFile>>,
+
+    parents: Vec<usize>,
+    nodes: HashMap<String, usize>,
+
+    instances: HashMap<String, Instance>,
+    components: Vec<ComponentInstance>,
+    signals: Vec<SignalDeclaration>,
+
+    /// Parts that exist without a block, e.g. from the standard library, by
+    /// name and ports.
+    external_parts: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Elaborator {
+    fn load(&mut self, path: &Path) -> Result<Rc<AtoFile>, AtopileError> {
+        if let Some(file) = self.files.get(path) {
+            return Ok(file.clone());
+        }
+
+        let source = std::fs::read_to_string(path)?;
+        let file = Rc::new(parser::parse(&path.display().to_string(), &source)?);
+        self.files.insert(path.to_path_buf(), file.clone());
+        Ok(file)
+    }
+
+    fn find_import(&self, from: &Path, import: &str) -> Option<PathBuf> {
+        from.parent()
+            .into_iter()
+            .map(|p| p.to_path_buf())
+            .chain(self.search_paths.iter().cloned())
+            .map(|dir| dir.join(import))
+            .find(|path| path.is_file())
+    }
+
+    /// Find the block a symbol refers to, from the file it's used in. Returns
+    /// `None` for imports that can't be found, which may refer to parts we
+    /// already know about.
+    fn resolve(
+        &mut self,
+        file_path: &Path,
+        symbol: &str,
+    ) -> Result<Option<ResolvedBlock>, AtopileError> {
+        let file = self.load(file_path)?;
+        if let Some(block) = file.block(symbol) {
+            return Ok(Some(ResolvedBlock {
+                file: file_path.to_path_buf(),
+                block: block.clone(),
+            }));
+        }
+
+        let import = file
+            .imports
+            .iter()
+            .find(|i| i.symbols.iter().any(|s| s == symbol))
+            .ok_or_else(|| {
+                AtopileError::ParseError(format!(
+                    "{}: {} is not defined or imported",
+                    file_path.display(),
+                    symbol
+                ))
+            })?;
+
+        match self.find_import(file_path, &import.path) {
+            Some(path) => self.resolve(&path, symbol),
+            None => Ok(None),
+        }
+    }
+
+    fn node(&mut self, key: &str) -> usize {
+        let next = self.parents.len();
+        let node = *self.nodes.entry(key.to_string()).or_insert(next);
+        if node == next {
+            self.parents.push(next);
+        }
+        node
+    }
+
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut node = node;
+        while self.parents[node] != root {
+            let next = self.parents[node];
+            self.parents[node] = root;
+            node = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a != b {
+            self.parents[a] = b;
+        }
+    }
+
+    fn declare_signal(&mut self, path: &str, name: &str, kind: AtoBlockKind, depth: usize) {
+        let key = join_path(path, name);
+        let is_new = !self.nodes.contains_key(&key);
+        let node = self.node(&key);
+
+        if let Some(instance) = self.instances.get_mut(path) {
+            if !instance.signals.iter().any(|s| s == name) {
+                instance.signals.push(name.to_string());
+            }
+        }
+
+        // Signals in modules and interfaces can name nets; signals inside
+        // components are only used for the ports of their part.
+        if is_new && kind != AtoBlockKind::Component {
+            let (name, depth) = match kind {
+                // Interface signals are named relative to the module holding
+                // the interface, e.g. `power.vcc`.
+                AtoBlockKind::Interface => (
+                    format!("{}.{}", path.rsplit('.').next().unwrap_or(path), name),
+                    depth.saturating_sub(1),
+                ),
+                _ => (name.to_string(), depth),
+            };
+            self.signals.push(SignalDeclaration {
+                node,
+                name,
+                path: key,
+                depth,
+            });
+        }
+    }
+
+    fn pin_key(path: &str, pin: &str) -> String {
+        format!("{}:{}", path, pin)
+    }
+
+    /// Resolve one side of a connection to the nodes it refers to, keyed by
+    /// signal name so that interfaces can be connected signal by signal.
+    fn resolve_endpoint(
+        &mut self,
+        path: &str,
+        kind: AtoBlockKind,
+        depth: usize,
+        endpoint: &AtoEndpoint,
+    ) -> Option<Vec<(String, usize)>> {
+        match endpoint {
+            AtoEndpoint::Signal(name) => {
+                self.declare_signal(path, name, kind, depth);
+                Some(vec![(String::new(), self.node(&join_path(path, name)))])
+            }
+            AtoEndpoint::Pin(pin) => {
+                self.declare_pin(path, pin);
+                Some(vec![(String::new(), self.node(&Self::pin_key(path, pin)))])
+            }
+            AtoEndpoint::Path(segments) => {
+                let key = join_path(path, &segments.join("."));
+                if let Some(instance) = self.instances.get(&key) {
+                    if instance.kind == InstanceKind::Interface {
+                        let signals = instance.signals.clone();
+                        return Some(
+                            signals
+                                .into_iter()
+                                .map(|s| {
+                                    let node = self.node(&join_path(&key, &s));
+                                    (s, node)
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+                if self.nodes.contains_key(&key) {
+                    return Some(vec![(String::new(), self.node(&key))]);
+                }
+
+                // A pin of a component instance, e.g. `r1.1`.
+                let (owner, pin) = segments.split_at(segments.len() - 1);
+                let pin_key = Self::pin_key(&join_path(path, &owner.join(".")), &pin[0]);
+                if self.nodes.contains_key(&pin_key) {
+                    return Some(vec![(String::new(), self.node(&pin_key))]);
+                }
+
+                None
+            }
+        }
+    }
+
+    fn declare_pin(&mut self, path: &str, pin: &str) {
+        self.node(&Self::pin_key(path, pin));
+        if let Some(InstanceKind::Component(index)) = self.instances.get(path).map(|i| i.kind) {
+            let ports = &mut self.components[index].ports;
+            if !ports.iter().any(|(p, _)| p == pin) {
+                ports.push((pin.to_string(), pin.to_string()));
+            }
+        }
+    }
+
+    /// Instantiate `symbol`, as used in `file`, at `path`. `module_name` is the
+    /// name of the enclosing module, which is used as the sheet name of the
+    /// components inside it.
+    fn instantiate(
+        &mut self,
+        file: &Path,
+        symbol: &str,
+        path: &str,
+        module_name: Option<&str>,
+        depth: usize,
+    ) -> Result<(), AtopileError> {
+        if depth > MAX_MODULE_DEPTH {
+            return Err(AtopileError::ParseError(format!(
+                "Module hierarchy is too deep at {}, is {} recursive?",
+                path, symbol
+            )));
+        }
+
+        let Some(resolved) = self.resolve(file, symbol)? else {
+            return self.instantiate_external(file, symbol, path, module_name);
+        };
+
+        // Collect the statements of the block and the blocks it inherits from.
+        let mut blocks = vec![resolved];
+        while let Some(parent) = blocks.last().unwrap().block.parent.clone() {
+            let last = blocks.last().unwrap();
+            let file = last.file.clone();
+            let resolved = self.resolve(&file, &parent)?.ok_or_else(|| {
+                AtopileError::ParseError(format!("Could not find {} to inherit from", parent))
+            })?;
+            blocks.push(resolved);
+            if blocks.len() > MAX_MODULE_DEPTH {
+                return Err(AtopileError::ParseError(format!(
+                    "{} inherits from itself",
+                    symbol
+                )));
+            }
+        }
+        let kind = blocks[0].block.kind;
+        let name = blocks[0].block.name.clone();
+
+        let instance_kind = match kind {
+            AtoBlockKind::Component => {
+                self.components.push(ComponentInstance {
+                    path: path.to_string(),
+                    part_name: name.clone(),
+                    designator: None,
+                    sheet_name: module_name.map(|m| m.to_string()),
+                    ports: vec![],
+                    pin_types: BTreeMap::new(),
+                    part_metadata: BTreeMap::new(),
+                    metadata: BTreeMap::new(),
+                });
+                InstanceKind::Component(self.components.len() - 1)
+            }
+            AtoBlockKind::Module => InstanceKind::Module,
+            AtoBlockKind::Interface => InstanceKind::Interface,
+        };
+        self.instances.insert(
+            path.to_string(),
+            Instance {
+                kind: instance_kind,
+                signals: vec![],
+            },
+        );
+
+        // The entry module doesn't count as a sheet.
+        let child_module_name = match (kind, depth) {
+            (AtoBlockKind::Module, 0) => None,
+            (AtoBlockKind::Module, _) => Some(name.as_str()),
+            _ => module_name,
+        };
+
+        for resolved in blocks.iter().rev() {
+            for statement in resolved.block.statements.iter() {
+                let error = |message: String| {
+                    AtopileError::ParseError(format!(
+                        "{}:{}: {}",
+                        resolved.file.display(),
+                        statement.line,
+                        message
+                    ))
+                };
+
+                if let InstanceKind::Component(index) = instance_kind {
+                    let pin_type = statement
+                        .comment
+                        .as_deref()
+                        .and_then(|c| c.strip_prefix(PIN_TYPE_COMMENT))
+                        .and_then(|t| t.trim().parse::<PinType>().ok());
+                    if let Some(pin_type) = pin_type {
+                        for pin in declared_pins(&statement.kind) {
+                            self.components[index]
+                                .pin_types
+                                .insert(pin.to_string(), pin_type);
+                        }
+                    }
+                }
+
+                match &statement.kind {
+                    AtoStatementKind::Signal(signal) => {
+                        self.declare_signal(path, signal, kind, depth);
+                    }
+                    AtoStatementKind::Pin(pin) => self.declare_pin(path, pin),
+                    AtoStatementKind::New { name, symbol } => {
+                        self.instantiate(
+                            &resolved.file,
+                            symbol,
+                            &join_path(path, name),
+                            child_module_name,
+                            depth + 1,
+                        )?;
+                    }
+                    AtoStatementKind::Connect(left, right) => {
+                        let left_nodes = self
+                            .resolve_endpoint(path, kind, depth, left)
+                            .ok_or_else(|| error(format!("Unknown signal {:?}", left)))?;
+                        let right_nodes = self
+                            .resolve_endpoint(path, kind, depth, right)
+                            .ok_or_else(|| error(format!("Unknown signal {:?}", right)))?;
+
+                        for (left_name, left_node) in left_nodes.iter() {
+                            for (right_name, right_node) in right_nodes.iter() {
+                                if left_name == right_name {
+                                    self.union(*left_node, *right_node);
+                                }
+                            }
+                        }
+                    }
+                    AtoStatementKind::Assign { target, value } => {
+                        let (owner, attribute) = target.split_at(target.len() - 1);
+                        let owner = match owner.is_empty() {
+                            true => path.to_string(),
+                            false => join_path(path, &owner.join(".")),
+                        };
+                        let Some(InstanceKind::Component(index)) =
+                            self.instances.get(&owner).map(|i| i.kind)
+                        else {
+                            continue;
+                        };
+
+                        let component = &mut self.components[index];
+                        if attribute[0] == "designator" {
+                            component.designator = Some(value.clone());
+                        } else if owner == path {
+                            component
+                                .part_metadata
+                                .insert(metadata_key(&attribute[0]), value.clone());
+                        } else {
+                            component
+                                .metadata
+                                .insert(metadata_key(&attribute[0]), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        // Name the ports of a component after the signals connected to its
+        // pins. This has to happen before the component is connected to
+        // anything outside of it.
+        if let InstanceKind::Component(index) = instance_kind {
+            let signals = self.instances[path].signals.clone();
+            let mut ports = self.components[index].ports.clone();
+            for (pin, signal_name) in ports.iter_mut() {
+                let pin_node = self.node(&Self::pin_key(path, pin));
+                let pin_root = self.find(pin_node);
+                let mut connected: Vec<&String> = vec![];
+                for signal in signals.iter() {
+                    let signal_node = self.node(&join_path(path, signal));
+                    if self.find(signal_node) == pin_root {
+                        connected.push(signal);
+                    }
+                }
+                connected.sort_by(|a, b| compare(a, b));
+                if let Some(signal) = connected.first() {
+                    *signal_name = signal.to_string();
+                }
+            }
+            self.components[index].ports = ports;
+        }
+
+        Ok(())
+    }
+
+    /// Instantiate a symbol whose import couldn't be found, using a part we
+    /// already know about with the same name, such as `Resistor` from the
+    /// generics library.
+    fn instantiate_external(
+        &mut self,
+        file: &Path,
+        symbol: &str,
+        path: &str,
+        module_name: Option<&str>,
+    ) -> Result<(), AtopileError> {
+        let ports = self.external_parts.get(symbol).cloned().ok_or_else(|| {
+            AtopileError::ParseError(format!(
+                "{}: Could not find the file defining {}",
+                file.display(),
+                symbol
+            ))
+        })?;
+
+        self.components.push(ComponentInstance {
+            path: path.to_string(),
+            part_name: symbol.to_string(),
+            designator: None,
+            sheet_name: module_name.map(|m| m.to_string()),
+            ports: ports.clone(),
+            pin_types: BTreeMap::new(),
+            part_metadata: BTreeMap::new(),
+            metadata: BTreeMap::new(),
+        });
+        self.instances.insert(
+            path.to_string(),
+            Instance {
+                kind: InstanceKind::Component(self.components.len() - 1),
+                signals: ports.iter().map(|(_, signal)| signal.clone()).collect(),
+            },
+        );
+
+        for (pin, signal) in ports.iter() {
+            let pin_node = self.node(&Self::pin_key(path, pin));
+            let signal_node = self.node(&join_path(path, signal));
+            self.union(pin_node, signal_node);
+        }
+
+        Ok(())
+    }
+
+    fn build(mut self, mut schematic: Schematic) -> Result<Schematic, AtopileError> {
+        // Register a Part for each component block. Blocks which share a name
+        // with a known part (e.g. the generic resistor) reuse it, as long as
+        // their ports match. Otherwise the block takes the place of the
+        // standard library part.
+        for component in self.components.iter() {
+            if let Some(part) = schematic.get_part(&component.part_name) {
+                let matches = {
+                    let part = part.as_deref();
+                    part.ports_by_terminal_identifier.len() == component.ports.len()
+                        && component.ports.iter().all(|(pin, signal)| {
+                            part.get_port(pin)
+                                .is_some_and(|p| p.as_deref().signal == *signal)
+                        })
+                };
+                if matches {
+                    continue;
+                }
+                if !schematic.unregister_standard_part(&component.part_name) {
+                    return Err(AtopileError::NameCollisionError(
+                        component.part_name.clone(),
+                    ));
+                }
+            }
+
+            let mut pb = PartBuilder::default();
+            pb.name(component.part_name.clone());
+            for (pin, signal) in component.ports.iter() {
+                let pin_type = component.pin_types.get(pin).copied().unwrap_or_default();
+                pb.port_with_type(pin, signal, pin_type);
+            }
+            for (key, value) in component.part_metadata.iter() {
+                pb.metadata(key, value);
+            }
+            schematic.add_part(pb.build()?)?;
+        }
+
+        // Register a Component for each instance, named after its designator
+        // if it has one.
+        let mut component_names = vec![];
+        for component in self.components.iter() {
+            let name = component
+                .designator
+                .clone()
+                .unwrap_or(component.path.clone());
+            let part = schematic
+                .get_part(&component.part_name)
+                .expect("part not found");
+
+            let mut cb = ComponentBuilder::default();
+            cb.name(name.clone());
+            cb.part(part);
+            // Quantities such as `resistance` are stored in a canonical form,
+            // as they are by the KiCad importers.
+            for (key, value) in component.metadata.iter() {
+                cb.metadata(key, &normalize(key, value));
+            }
+            if let Some(sheet_name) = component.sheet_name.as_ref() {
+                cb.metadata(KICAD_SHEET_NAME_KEY, sheet_name);
+            }
+            cb.metadata(KICAD_SHEET_PATH_KEY, &sheet_path(&component.path));
+            schematic.add_component(cb.build()?)?;
+            component_names.push(name);
+        }
+
+        // Group the pins of every component by net.
+        let pins: Vec<(usize, String, String, String)> = self
+            .components
+            .iter()
+            .zip(component_names.iter())
+            .flat_map(|(component, name)| {
+                component.ports.iter().map(|(pin, signal)| {
+                    let node = self.nodes[&Self::pin_key(&component.path, pin)];
+                    (node, name.clone(), pin.clone(), signal.clone())
+                })
+            })
+            .collect();
+        let mut pins_by_net: BTreeMap<usize, Vec<(String, String, String)>> = BTreeMap::new();
+        for (node, component, pin, signal) in pins {
+            let root = self.find(node);
+            pins_by_net
+                .entry(root)
+                .or_default()
+                .push((component, pin, signal));
+        }
+
+        // Name each net after its shallowest signal, falling back to the full
+        // path of the signal if that name is used by several nets.
+        let mut names_by_net: HashMap<usize, (String, String)> = HashMap::new();
+        let mut signals = std::mem::take(&mut self.signals);
+        signals.sort_by(|a, b| {
+            a.depth
+                .cmp(&b.depth)
+                .then_with(|| compare(&a.name, &b.name))
+        });
+        for signal in signals.iter() {
+            let root = self.find(signal.node);
+            names_by_net
+                .entry(root)
+                .or_insert((signal.name.clone(), signal.path.clone()));
+        }
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for root in pins_by_net.keys() {
+            if let Some((name, _)) = names_by_net.get(root) {
+                *name_counts.entry(name.as_str()).or_default() += 1;
+            }
+        }
+
+        for (root, pins) in pins_by_net.iter() {
+            let name = match names_by_net.get(root) {
+                Some((name, _)) if name_counts[name.as_str()] == 1 => name.clone(),
+                Some((_, path)) => path.clone(),
+                None => {
+                    // Pins which aren't connected to anything are left out.
+                    if pins.len() < 2 {
+                        continue;
+                    }
+                    let (component, pin, signal) = pins
+                        .iter()
+                        .min_by(|a, b| compare(&a.0, &b.0).then_with(|| compare(&a.1, &b.1)))
+                        .unwrap();
+                    match signal == pin {
+                        true => format!("Net-({}-Pad{})", component, pin),
+                        false => format!("Net-({}-{})", component, signal),
+                    }
+                }
+            };
+
+            let net = schematic.add_net(NetBuilder::default().name(name).build()?)?;
+            for (component, pin, _) in pins.iter() {
+                schematic.connect(&net.name(), component, pin)?;
+            }
+        }
+
+        schematic.infer_net_types(&NetTypeOverrides::default())?;
+
+        Ok(schematic)
+    }
+}
+
+/// Find the root of the Atopile project containing `path`, i.e. the closest
+/// directory with an `ato.yaml`.
+fn find_project_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join("ato.yaml").is_file())
+        .map(|dir| dir.to_path_buf())
+}
+
+/// Import the module `module` defined in (or imported by) the `.ato` file at
+/// `file` into a Schematic.
+pub fn schematics_from_atopile_module(
+    file: &Path,
+    module: &str,
+) -> Result<Schematic, AtopileError> {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library()?;
+
+    let mut search_paths = vec![];
+    if let Some(root) = find_project_root(file) {
+        search_paths.push(root.join("elec").join("src"));
+        search_paths.push(root.join(".ato").join("modules"));
+    }
+
+    let mut elaborator = Elaborator {
+        search_paths,
+        files: HashMap::new(),
+        parents: vec![],
+        nodes: HashMap::new(),
+        instances: HashMap::new(),
+        components: vec![],
+        signals: vec![],
+        external_parts: schematic
+            .parts_iter()
+            .map(|part| {
+                let part = part.as_deref();
+                let ports = part
+                    .ports_by_terminal_identifier
+                    .values()
+                    .map(|p| {
+                        let p = p.as_deref();
+                        (p.terminal_identifier.clone(), p.signal.clone())
+                    })
+                    .collect();
+                (part.name.clone(), ports)
+            })
+            .collect(),
+    };
+
+    elaborator.instantiate(file, module, "", None, 0)?;
+    elaborator.build(schematic)
+}
+
+/// Import an Atopile project into a Schematic, starting from the entry of the
+/// `default` build in its `ato.yaml` (or the first build, if there's no
+/// default).
+pub fn schematics_from_atopile_project(project_dir: &Path) -> Result<Schematic, AtopileError> {
+    let config = std::fs::read_to_string(project_dir.join("ato.yaml"))?;
+    let config: AtoConfig = serde_yaml::from_str(&config)
+        .map_err(|e| AtopileError::ParseError(format!("ato.yaml: {}", e)))?;
+
+    let build = config
+        .builds
+        .get("default")
+        .or(config.builds.values().next())
+        .ok_or_else(|| AtopileError::ParseError("ato.yaml: no builds defined".to_string()))?;
+
+    let (file, module) = build.entry.rsplit_once(':').ok_or_else(|| {
+        AtopileError::ParseError(format!(
+            "ato.yaml: entry \"{}\" should look like file.ato:Module",
+            build.entry
+        ))
+    })?;
+
+    schematics_from_atopile_module(&project_dir.join(file), module)
+}
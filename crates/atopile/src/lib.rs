@@ -1,18 +1,46 @@
+mod generics;
+mod hierarchy;
+mod importer;
+mod merge;
 mod normalizer;
+mod parser;
+mod scaffold;
 mod writer;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     io::Write,
+    path::{Path, PathBuf},
 };
 
+use generics::find_generic;
+use hierarchy::{SheetTree, ROOT};
 use natord::compare;
-use schematics::{component::ComponentRef, part::PartRef, Normalizer, Schematic, SchematicError};
+use schematics::{
+    component::ComponentRef,
+    part::{PartRef, PinType},
+    standard_library, Normalizer, Schematic, SchematicError,
+};
 use thiserror::Error;
 use writer::AtopileWriter;
 
+pub use importer::{schematics_from_atopile_module, schematics_from_atopile_project};
+pub use merge::{MergePlan, MergeReport};
 pub use normalizer::AtopileNormalizer;
 
+/// Atopile has no syntax for the electrical type of a pin, so it is written as
+/// a comment, e.g. `vcc ~ pin 1  # pin_type: power_in`.
+const PIN_TYPE_COMMENT: &str = "pin_type:";
+
+/// Write `contents` to `path`, creating its directory if needed.
+fn write_file(path: &Path, contents: &str) -> Result<(), AtopileError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
 #[derive(Error, Debug)]
 pub enum AtopileError {
     #[error("Schematic error: {0}")]
@@ -23,6 +51,9 @@ pub enum AtopileError {
 
     #[error("Name collision: {0}")]
     NameCollisionError(String),
+
+    #[error("Parse error: {0}")]
+    ParseError(String),
 }
 
 pub struct AtopileProject {
@@ -163,24 +194,29 @@ pub struct AtopileModule {
     /// The nets defined in this module, defined as a map from the net name to
     /// a list of ports it connects.
     nets: HashMap<String, Vec<String>>,
+
+    /// Designators to set on components inside the module's instances, as
+    /// pairs of the component's path and its designator.
+    designators: Vec<(String, String)>,
+
+    /// The nets which are connected outside the module, so are declared even
+    /// if they only connect a single port inside it.
+    interface: HashSet<String>,
 }
 
-impl AtopileProject {
-    fn sheet_for_component(&self, component: &ComponentRef) -> String {
-        let sheet_name = component
-            .as_deref()
-            .metadata
-            .get("Sheetname")
-            .cloned()
-            .map(|s| s.to_string())
-            .unwrap_or(self.name.clone());
-
-        // Normalize the sheet name.
-        AtopileNormalizer::default()
-            .normalize_part_name(&sheet_name)
-            .expect("failed to normalize sheet name")
+/// Returns `name`, or `name` with a number appended if it's already taken,
+/// and marks it as taken.
+fn unique_name(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{}{}", name, n);
+        n += 1;
     }
+    candidate
+}
 
+impl AtopileProject {
     pub fn from_schematic(name: String, schematic: &Schematic) -> Result<Self, AtopileError> {
         // Capitalize the first letter of the project name.
         let name = name.chars().next().unwrap().to_uppercase().to_string() + &name[1..];
@@ -192,11 +228,25 @@ impl AtopileProject {
             symbol_name_to_file_name: HashMap::new(),
         };
 
-        // Keep track of all of the sheet names we've seen.
-        let mut sheet_names = HashSet::<String>::new();
-
-        // Create a library file for each part.
+        // Create a library file for each part. Parts from Atopile's generics
+        // library are imported from there instead, and standard library parts
+        // that aren't used are left out.
+        let used_parts: HashSet<String> = schematic
+            .components_iter()
+            .map(|c| c.as_deref().part.as_deref().name.clone())
+            .collect();
         for part in schematic.parts_iter() {
+            let part_name = part.as_deref().name.clone();
+            if standard_library::is_standard_part(&part_name) && !used_parts.contains(&part_name) {
+                continue;
+            }
+            if let Some(generic) = find_generic(&part.as_deref()) {
+                project
+                    .symbol_name_to_file_name
+                    .insert(part.as_deref().name.clone(), generic.file.to_string());
+                continue;
+            }
+
             let mut signals = HashMap::new();
             for (pin_name, port) in part.as_deref().ports_by_terminal_identifier.iter() {
                 signals
@@ -214,129 +264,191 @@ impl AtopileProject {
             project.define_symbol(format!("library/{}.ato", part.as_deref().name), symbol)?;
         }
 
-        // Creaet a module for each sheet, and instantiate each component in the
-        // sheet.
-        for component in schematic.components_iter() {
-            // Use the sheet name as the module name.
-            let sheet_name = project.sheet_for_component(component);
-            sheet_names.insert(sheet_name.clone());
-
-            // Create a module for the sheet if we don't have one.
-            if project.find_module(&sheet_name).is_none() {
-                let module = AtopileModule {
-                    name: sheet_name.to_string(),
-                    definitions: vec![],
-                    nets: HashMap::new(),
-                };
-                project
-                    .define_symbol(format!("{}.ato", sheet_name), AtopileSymbol::Module(module))?;
-            }
+        // Create a module for each sheet, instantiated once for every use of
+        // the sheet, and a root module for the components on the root sheet.
+        let tree = SheetTree::from_schematic(schematic);
+        let normalizer = AtopileNormalizer::default();
+        let normalize = |name: &str| {
+            normalizer
+                .normalize_part_name(name)
+                .expect("failed to normalize sheet name")
+        };
+        let instance_name = |index: usize| normalize(&tree.instances[index].name);
+
+        let mut taken_names: HashSet<String> =
+            project.symbol_name_to_file_name.keys().cloned().collect();
+        taken_names.insert(name.clone());
+        let module_names: Vec<String> = tree
+            .modules
+            .iter()
+            .map(|m| unique_name(normalize(&tree.module_name(m)), &mut taken_names))
+            .collect();
+        let module_name = |index: usize| match tree.instances[index].module {
+            Some(module) => module_names[module].clone(),
+            None => name.clone(),
+        };
 
-            // Add a definition for the component.
-            let module = project.find_module(&sheet_name).expect("module not found");
-            module.definitions.push(AtopileDefinition {
-                name: component.as_deref().name.clone(),
-                symbol_name: component.as_deref().part.as_deref().name.clone(),
-                component: Some(component.clone()),
-            });
-        }
+        let mut written: Vec<usize> = vec![ROOT];
+        written.extend(tree.modules.iter().map(|m| m.representative));
+        for index in written {
+            let instance = &tree.instances[index];
+            let mut definitions: Vec<AtopileDefinition> = instance
+                .components
+                .iter()
+                .map(|component| AtopileDefinition {
+                    name: component.as_deref().name.clone(),
+                    symbol_name: component.as_deref().part.as_deref().name.clone(),
+                    component: Some(component.clone()),
+                })
+                .collect();
+            definitions.extend(instance.children.iter().map(|child| AtopileDefinition {
+                name: instance_name(*child),
+                symbol_name: module_name(*child),
+                component: None,
+            }));
 
-        // Create a module for the root.
-        project.define_symbol(
-            format!("{}.ato", project.name.to_lowercase()),
-            AtopileSymbol::Module(AtopileModule {
-                name: project.name.clone(),
-                definitions: vec![],
+            let module = AtopileModule {
+                name: module_name(index),
+                definitions,
                 nets: HashMap::new(),
-            }),
-        )?;
-
-        // Parse the nets, and put them in a sheet-specific module or the root
-        // module, depending on how far they spean.
-        for net in schematic.nets_iter() {
-            let net_name = net.name();
-            let sheet_names = net
-                .as_deref()
-                .connections
-                .iter()
-                .map(|(c, _)| project.sheet_for_component(c))
-                .collect::<Vec<String>>();
-
-            // If all of the components in the net are in the same sheet, we'll
-            // put the net in that module. Otherwise, we'll put it in the root.
-            let place_in_root =
-                sheet_names.len() == 0 || !sheet_names.iter().all(|s| s == &sheet_names[0]);
-            let net_module = if place_in_root {
-                &project.name.clone()
-            } else {
-                &sheet_names[0]
+                designators: vec![],
+                interface: HashSet::new(),
             };
+            let filename = match index {
+                ROOT => project.root_filename(),
+                _ => format!("{}.ato", module.name),
+            };
+            project.define_symbol(filename, AtopileSymbol::Module(module))?;
+        }
 
-            let component_to_sheet = net
-                .as_deref()
-                .connections
-                .iter()
-                .map(|(c, _)| (c.clone(), project.sheet_for_component(c)))
-                .collect::<HashMap<_, _>>();
-
-            let module = project.find_module(net_module).expect("module not found");
-
-            for (component, port) in net.as_deref().connections.iter() {
-                let connections = module.nets.entry(net_name.clone()).or_insert_with(Vec::new);
-
-                if place_in_root {
-                    connections.push(format!(
-                        "{}.{}.{}",
-                        component_to_sheet[component],
-                        component.as_deref().name,
-                        port.as_deref().signal
-                    ));
-                } else {
-                    connections.push(format!(
-                        "{}.{}",
-                        component.as_deref().name,
-                        port.as_deref().signal
-                    ));
-                }
+        // The modules of sheets used more than once are written with the
+        // designators of their first instance, so set the rest from the root.
+        let mut designators = vec![];
+        for component in schematic.components_iter() {
+            let local_name = tree.local_name(component);
+            if local_name == component.as_deref().name {
+                continue;
             }
+            let mut path: Vec<String> = tree
+                .ancestors(tree.instance_of(component))
+                .into_iter()
+                .map(instance_name)
+                .collect();
+            path.push(local_name);
+            designators.push((path.join("."), component.as_deref().name.clone()));
         }
-
-        // Finally, add the sheet module definitions to the root.
-        let root_module = project.find_module(&name).expect("root module not found");
-        for sheet_name in sheet_names.iter() {
-            root_module.definitions.push(AtopileDefinition {
-                name: sheet_name.to_string(),
-                symbol_name: sheet_name.to_string(),
-                component: None,
+        project
+            .find_module(&name)
+            .expect("root module not found")
+            .designators = designators;
+
+        // Each module declares a signal for every group of pins connected in
+        // all of its instances. Those connected outside the module make up its
+        // interface, which the parent module connects to.
+        let signals = tree.module_signals(schematic);
+        let mut signal_names: HashMap<usize, Vec<String>> = HashMap::new();
+        for index in tree.written_instances() {
+            let module = project
+                .find_module(&module_name(index))
+                .expect("module not found");
+            let mut taken_names: HashSet<String> =
+                module.definitions.iter().map(|d| d.name.clone()).collect();
+
+            // Nets named after a label start with the path of the label's
+            // sheet, which is left out inside the modules of the sheet and the
+            // sheets below it.
+            let sheet_path: Vec<String> = tree
+                .ancestors(index)
+                .into_iter()
+                .map(|i| tree.instances[i].name.clone())
+                .collect();
+            let prefixes: Vec<String> = (1..=sheet_path.len())
+                .rev()
+                .filter_map(|depth| {
+                    normalizer
+                        .normalize_net_name(&format!("/{}/", sheet_path[..depth].join("/")))
+                        .ok()
+                })
+                .collect();
+
+            let mut names = vec![String::new(); signals[&index].len()];
+            let mut order: Vec<usize> = (0..names.len()).collect();
+            order.sort_by(|a, b| {
+                compare(
+                    &signals[&index][*a].net.name(),
+                    &signals[&index][*b].net.name(),
+                )
             });
+            for s in order {
+                let signal = &signals[&index][s];
+                let net_name = signal.net.name();
+                let local_name = prefixes
+                    .iter()
+                    .filter_map(|prefix| net_name.strip_prefix(prefix.as_str()))
+                    .find(|n| n.starts_with(|c: char| c.is_ascii_alphabetic()))
+                    .unwrap_or(&net_name)
+                    .to_string();
+                let name = unique_name(local_name, &mut taken_names);
+
+                let ports = signal
+                    .ports
+                    .iter()
+                    .map(|(component, port)| format!("{}.{}", component, port))
+                    .chain(signal.children.iter().map(|(child, child_signal)| {
+                        let representative = tree.representative(*child);
+                        format!(
+                            "{}.{}",
+                            instance_name(*child),
+                            signal_names[&representative][*child_signal]
+                        )
+                    }))
+                    .collect();
+                module.nets.insert(name.clone(), ports);
+                if signal.interface {
+                    module.interface.insert(name.clone());
+                }
+                names[s] = name;
+            }
+            signal_names.insert(index, names);
         }
 
         Ok(project)
     }
 
-    pub fn generate_to_directory(
-        &self,
-        output_dir: &std::path::PathBuf,
-    ) -> Result<(), AtopileError> {
+    /// The file the root module is written to.
+    fn root_filename(&self) -> String {
+        format!("{}.ato", self.name.to_lowercase())
+    }
+
+    /// The entry point of the project, as given in `ato.yaml`, e.g.
+    /// `elec/src/blinky.ato:Blinky`.
+    pub fn entry(&self) -> String {
+        format!("elec/src/{}:{}", self.root_filename(), self.name)
+    }
+
+    /// Render every file of the project, keyed by its path relative to the
+    /// project directory, e.g. `elec/src/generics/resistors.ato`.
+    pub fn generate_files(&self) -> Result<BTreeMap<PathBuf, String>, AtopileError> {
+        let mut files = BTreeMap::new();
         for (filename, file) in &self.files_by_name {
-            let file_path = output_dir.join("elec").join("src").join(filename);
-            self.write_file(&file, &file_path)?;
+            let path = Path::new("elec").join("src").join(filename);
+            files.insert(path, self.render_file(file)?);
         }
-
-        Ok(())
+        Ok(files)
     }
 
-    fn write_file(
-        &self,
-        atopile_file: &AtopileFile,
-        path: &std::path::PathBuf,
-    ) -> Result<(), AtopileError> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+    /// Write the project's files to `output_dir`, replacing any that exist.
+    pub fn generate_to_directory(&self, output_dir: &Path) -> Result<(), AtopileError> {
+        let files = self.generate_files()?;
+        for (path, contents) in files.iter() {
+            write_file(&output_dir.join(path), contents)?;
         }
+        merge::save_base(output_dir, &files)
+    }
 
-        let file = std::fs::File::create(path)?;
-        let mut writer = AtopileWriter::new(file);
+    fn render_file(&self, atopile_file: &AtopileFile) -> Result<String, AtopileError> {
+        let mut contents = vec![];
+        let mut writer = AtopileWriter::new(&mut contents);
 
         let mut imports: Vec<_> = self
             .collect_imports(&atopile_file.filename)
@@ -363,7 +475,8 @@ impl AtopileProject {
             }
         }
 
-        Ok(())
+        drop(writer);
+        Ok(String::from_utf8(contents).expect("generated files are UTF-8"))
     }
 
     fn write_component<T: Write>(
@@ -379,7 +492,18 @@ impl AtopileProject {
             let mut sorted_pin_names = component.signals[*signal_name].clone();
             sorted_pin_names.sort_by(|a, b| compare(a, b));
             for pin_name in sorted_pin_names.iter() {
-                writer.write_line(&format!("{} ~ pin {}", signal_name, pin_name))?;
+                let pin_type = component
+                    .part
+                    .as_deref()
+                    .get_port(pin_name)
+                    .map(|p| p.as_deref().pin_type)
+                    .unwrap_or_default();
+                let line = format!("{} ~ pin {}", signal_name, pin_name);
+                match pin_type {
+                    PinType::Unspecified => writer.write_line(&line)?,
+                    _ => writer
+                        .write_line(&format!("{}  # {} {}", line, PIN_TYPE_COMMENT, pin_type))?,
+                }
             }
             writer.write_line("")?;
         }
@@ -387,6 +511,11 @@ impl AtopileProject {
         let mpn = component.part.as_deref().metadata.get("MPN").cloned();
         let footprint = component.part.as_deref().metadata.get("Footprint").cloned();
 
+        // Blocks can't be empty, e.g. for a mounting hole.
+        if component.signals.is_empty() && mpn.is_none() && footprint.is_none() {
+            writer.write_line("pass")?;
+        }
+
         if let Some(mpn) = mpn {
             writer.write_line(&format!("mpn = \"{}\"", mpn))?;
         }
@@ -399,6 +528,42 @@ impl AtopileProject {
         Ok(())
     }
 
+    /// Write the attributes of a component using a standard library part,
+    /// which only the component holds: the footprint and value, e.g.
+    /// `r1.resistance = 10kohm`. The original value is kept as a `value`
+    /// string, which is all there is for values Atopile can't parse.
+    fn write_standard_part_attributes<T: Write>(
+        &self,
+        name: &str,
+        component: &ComponentRef,
+        writer: &mut AtopileWriter<T>,
+    ) -> Result<(), AtopileError> {
+        let component = component.as_deref();
+        let part_name = component.part.as_deref().name.clone();
+        if !standard_library::is_standard_part(&part_name) {
+            return Ok(());
+        }
+
+        let metadata = |key: &str| component.metadata.get(key).filter(|v| !v.is_empty());
+        if let Some(footprint) = metadata("Footprint") {
+            writer.write_line(&format!("{}.footprint = \"{}\"", name, footprint))?;
+        }
+
+        let mut original = metadata("Value").cloned();
+        if let Some((key, value)) =
+            standard_library::value_key(&part_name).and_then(|key| Some((key, metadata(key)?)))
+        {
+            match generics::quantity(value, key) {
+                Some(quantity) => writer.write_line(&format!("{}.{} = {}", name, key, quantity))?,
+                None => original = original.or(Some(value.clone())),
+            }
+        }
+        if let Some(original) = original {
+            writer.write_line(&format!("{}.value = \"{}\"", name, original))?;
+        }
+        Ok(())
+    }
+
     fn write_module<T: Write>(
         &self,
         module: &AtopileModule,
@@ -413,16 +578,26 @@ impl AtopileProject {
                 definition.name, definition.symbol_name
             ))?;
 
-            if definition.component.is_some() {
+            if let Some(component) = definition.component.as_ref() {
                 writer.write_line(&format!(
                     "{}.designator = \"{}\"",
                     definition.name, definition.name
                 ))?;
+                self.write_standard_part_attributes(&definition.name, component, writer)?;
             }
 
             writer.ensure_break()?;
         }
 
+        let mut sorted_designators = module.designators.clone();
+        sorted_designators.sort_by(|a, b| compare(&a.0, &b.0));
+        for (path, designator) in sorted_designators.iter() {
+            writer.write_line(&format!("{}.designator = \"{}\"", path, designator))?;
+        }
+        if !sorted_designators.is_empty() {
+            writer.ensure_break()?;
+        }
+
         let mut sorted_net_names = module.nets.keys().collect::<Vec<&String>>();
         sorted_net_names.sort_by(|a, b| compare(a, b));
         for net_name in sorted_net_names.iter() {
@@ -430,7 +605,7 @@ impl AtopileProject {
             sorted_ports.sort();
             sorted_ports.dedup();
 
-            if sorted_ports.len() < 2 {
+            if sorted_ports.len() < 2 && !module.interface.contains(*net_name) {
                 // Don't bother describing unused nets.
                 continue;
             }
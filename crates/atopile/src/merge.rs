@@ -0,0 +1,271 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{parser, write_file, AtopileError, AtopileProject};
+
+/// Where a copy of the files as they were last generated is kept, relative to
+/// the project directory. It's the common ancestor when merging a new
+/// conversion with edits made since the last one.
+pub const BASE_DIR: &str = ".diode/base";
+
+/// What happened to each file when merging a conversion into a project.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeReport {
+    /// Files that didn't exist yet.
+    pub created: Vec<PathBuf>,
+
+    /// Files that changed, with any edits made to them kept.
+    pub updated: Vec<PathBuf>,
+
+    /// Files whose edits overlap with changes from the conversion. They're
+    /// written with conflict markers around the overlapping lines.
+    pub conflicted: Vec<PathBuf>,
+
+    /// Files that are no longer generated and hadn't been edited.
+    pub removed: Vec<PathBuf>,
+
+    /// Files that are no longer generated but were kept, since they had been
+    /// edited.
+    pub kept: Vec<PathBuf>,
+}
+
+impl MergeReport {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicted.is_empty()
+    }
+}
+
+/// The paths of the files below `dir`, relative to `root`.
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), AtopileError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, AtopileError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace the copy of the generated files kept in the project with `files`.
+pub(crate) fn save_base(
+    project_dir: &Path,
+    files: &BTreeMap<PathBuf, String>,
+) -> Result<(), AtopileError> {
+    let base_dir = project_dir.join(BASE_DIR);
+    if base_dir.exists() {
+        std::fs::remove_dir_all(&base_dir)?;
+    }
+    for (path, contents) in files.iter() {
+        write_file(&base_dir.join(path), contents)?;
+    }
+    Ok(())
+}
+
+/// Split an Atopile file into the lines before its first block, keyed by an
+/// empty name, followed by each block keyed by its name. The comment lines
+/// right above a block belong to it. Returns `None` if the file doesn't parse.
+fn split_blocks(path: &Path, source: &str) -> Option<Vec<(String, String)>> {
+    let ato_file = parser::parse(&path.display().to_string(), source).ok()?;
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut starts = vec![];
+    let mut previous = 0;
+    for block in ato_file.blocks.iter() {
+        let mut start = block.line - 1;
+        while start > previous && lines[start - 1].starts_with('#') {
+            start -= 1;
+        }
+        starts.push((block.name.clone(), start));
+        previous = block.line;
+    }
+
+    let mut regions = vec![];
+    let mut name = String::new();
+    let mut start = 0;
+    for (next_name, end) in starts {
+        regions.push((name, start..end));
+        name = next_name;
+        start = end;
+    }
+    regions.push((name, start..lines.len()));
+
+    Some(
+        regions
+            .into_iter()
+            .map(|(name, range)| {
+                let text = lines[range].join("\n");
+                (name, format!("{}\n", text.trim_end()))
+            })
+            .collect(),
+    )
+}
+
+/// Merge two versions of an Atopile file that have no common ancestor. With
+/// no way to tell which side changed a line, they're merged a block at a
+/// time: blocks that are the same on both sides, or only on one of them, are
+/// kept, and blocks that differ are conflicts. The imports are combined.
+/// Files that aren't Atopile, or don't parse, conflict as a whole.
+fn merge_blocks(path: &Path, ours: &str, theirs: &str) -> Result<String, String> {
+    let is_ato = path.extension().is_some_and(|e| e == "ato");
+    let (Some(ours_blocks), Some(theirs_blocks)) = (
+        is_ato.then(|| split_blocks(path, ours)).flatten(),
+        is_ato.then(|| split_blocks(path, theirs)).flatten(),
+    ) else {
+        return diffy::merge("", ours, theirs);
+    };
+
+    let mut conflicted = false;
+    let mut merged = vec![];
+    for (name, theirs_text) in theirs_blocks.iter() {
+        let Some((_, ours_text)) = ours_blocks.iter().find(|(n, _)| n == name) else {
+            merged.push(theirs_text.clone());
+            continue;
+        };
+        if name.is_empty() {
+            let mut lines: Vec<&str> = theirs_text.lines().collect();
+            for line in ours_text.lines() {
+                if !line.trim().is_empty() && !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+            merged.push(format!("{}\n", lines.join("\n")));
+        } else if ours_text == theirs_text {
+            merged.push(theirs_text.clone());
+        } else {
+            conflicted = true;
+            merged.push(format!(
+                "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+                ours_text, theirs_text
+            ));
+        }
+    }
+    for (name, ours_text) in ours_blocks.iter() {
+        if !theirs_blocks.iter().any(|(n, _)| n == name) {
+            merged.push(ours_text.clone());
+        }
+    }
+
+    let merged = merged
+        .into_iter()
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if conflicted {
+        Err(merged)
+    } else {
+        Ok(merged)
+    }
+}
+
+/// Merge the changes between `base` and `theirs` into `ours`, line by line.
+/// Without a base, e.g. in a project converted before bases were kept, see
+/// `merge_blocks`.
+fn merge_file(path: &Path, base: Option<&str>, ours: &str, theirs: &str) -> Result<String, String> {
+    if ours == theirs || base == Some(ours) {
+        return Ok(theirs.to_string());
+    }
+    if base == Some(theirs) {
+        return Ok(ours.to_string());
+    }
+    match base {
+        Some(base) => diffy::merge(base, ours, theirs),
+        None => merge_blocks(path, ours, theirs),
+    }
+}
+
+/// The files a merge would write and remove, before it's applied.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergePlan {
+    /// The contents to write to each file that changes, keyed by its path
+    /// relative to the project directory.
+    pub writes: BTreeMap<PathBuf, String>,
+
+    pub report: MergeReport,
+
+    /// The files as generated, which become the base of the next merge.
+    pub generated: BTreeMap<PathBuf, String>,
+}
+
+impl AtopileProject {
+    /// Work out how the project's files would be merged into `project_dir`,
+    /// without changing anything. See `merge_into_directory`.
+    pub fn plan_merge(&self, project_dir: &Path) -> Result<MergePlan, AtopileError> {
+        let files = self.generate_files()?;
+        let base_dir = project_dir.join(BASE_DIR);
+        let mut plan = MergePlan::default();
+
+        for (path, theirs) in files.iter() {
+            let Some(ours) = read_optional(&project_dir.join(path))? else {
+                plan.writes.insert(path.clone(), theirs.clone());
+                plan.report.created.push(path.clone());
+                continue;
+            };
+
+            let base = read_optional(&base_dir.join(path))?;
+            let merged = match merge_file(path, base.as_deref(), &ours, theirs) {
+                Ok(merged) => merged,
+                Err(conflicted) => {
+                    plan.report.conflicted.push(path.clone());
+                    conflicted
+                }
+            };
+            if merged != ours {
+                if !plan.report.conflicted.contains(path) {
+                    plan.report.updated.push(path.clone());
+                }
+                plan.writes.insert(path.clone(), merged);
+            }
+        }
+
+        // Remove the files from the last conversion that are no longer
+        // generated, unless they've been edited since.
+        let mut previous = vec![];
+        if base_dir.is_dir() {
+            list_files(&base_dir, &base_dir, &mut previous)?;
+        }
+        previous.sort();
+        for path in previous {
+            if files.contains_key(&path) {
+                continue;
+            }
+            let Some(ours) = read_optional(&project_dir.join(&path))? else {
+                continue;
+            };
+            if read_optional(&base_dir.join(&path))?.as_deref() == Some(ours.as_str()) {
+                plan.report.removed.push(path);
+            } else {
+                plan.report.kept.push(path);
+            }
+        }
+
+        plan.generated = files;
+        Ok(plan)
+    }
+
+    /// Write the project's files to `project_dir`, keeping the edits made to
+    /// them since they were last generated. Only the lines that changed in
+    /// the conversion are updated.
+    pub fn merge_into_directory(&self, project_dir: &Path) -> Result<MergeReport, AtopileError> {
+        let plan = self.plan_merge(project_dir)?;
+        for (path, contents) in plan.writes.iter() {
+            write_file(&project_dir.join(path), contents)?;
+        }
+        for path in plan.report.removed.iter() {
+            std::fs::remove_file(project_dir.join(path))?;
+        }
+        save_base(project_dir, &plan.generated)?;
+        Ok(plan.report)
+    }
+}
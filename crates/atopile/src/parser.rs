@@ -0,0 +1,369 @@
+//! A parser for the subset of the Atopile language needed to recover a
+//! netlist: imports, component/module/interface blocks, signal and pin
+//! declarations, instantiations, connections and assignments. Anything else
+//! (assertions, type annotations, specializations) is skipped.
+
+use crate::AtopileError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtoFile {
+    pub imports: Vec<AtoImport>,
+    pub blocks: Vec<AtoBlock>,
+}
+
+impl AtoFile {
+    pub fn block(&self, name: &str) -> Option<&AtoBlock> {
+        self.blocks.iter().find(|b| b.name == name)
+    }
+}
+
+/// `from "path.ato" import A, B`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtoImport {
+    pub path: String,
+    pub symbols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtoBlockKind {
+    Component,
+    Module,
+    Interface,
+}
+
+/// `component Name from Parent:` followed by an indented body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtoBlock {
+    /// The line of the block's header.
+    pub line: usize,
+    pub kind: AtoBlockKind,
+    pub name: String,
+    pub parent: Option<String>,
+    pub statements: Vec<AtoStatement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtoStatement {
+    pub line: usize,
+    pub kind: AtoStatementKind,
+
+    /// The trailing `#` comment on the statement's line, if any.
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtoStatementKind {
+    /// `signal name`
+    Signal(String),
+
+    /// `pin 1`
+    Pin(String),
+
+    /// `a ~ b`
+    Connect(AtoEndpoint, AtoEndpoint),
+
+    /// `name = new Symbol`
+    New { name: String, symbol: String },
+
+    /// `a.b = value`, with quotes removed from string values.
+    Assign { target: Vec<String>, value: String },
+}
+
+/// One side of a connection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtoEndpoint {
+    /// `signal name`, which also declares the signal.
+    Signal(String),
+
+    /// `pin 1`, which also declares the pin.
+    Pin(String),
+
+    /// A reference to an existing signal, pin or interface, e.g. `r1.p1`.
+    Path(Vec<String>),
+}
+
+fn parse_error(file: &str, line: usize, message: &str) -> AtopileError {
+    AtopileError::ParseError(format!("{}:{}: {}", file, line, message))
+}
+
+/// Strip a trailing `#` comment, ignoring `#` inside strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Find the first occurrence of `pattern` outside of a string.
+fn find_unquoted(text: &str, pattern: &str) -> Option<usize> {
+    let mut in_string = false;
+    for (i, c) in text.char_indices() {
+        if c == '"' {
+            in_string = !in_string;
+        } else if !in_string && text[i..].starts_with(pattern) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn split_unquoted<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(i) = find_unquoted(rest, separator) {
+        parts.push(&rest[..i]);
+        rest = &rest[i + separator.len()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner.to_string(),
+        None => value.to_string(),
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_path(file: &str, line: usize, text: &str) -> Result<Vec<String>, AtopileError> {
+    let segments: Vec<String> = text.trim().split('.').map(|s| s.to_string()).collect();
+    // Pins may be referenced by number, e.g. `r1.1`.
+    let valid = segments.iter().enumerate().all(|(i, s)| {
+        is_identifier(s) || (i > 0 && !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()))
+    });
+    if !valid {
+        return Err(parse_error(
+            file,
+            line,
+            &format!("Invalid reference \"{}\"", text.trim()),
+        ));
+    }
+    Ok(segments)
+}
+
+fn parse_endpoint(file: &str, line: usize, text: &str) -> Result<AtoEndpoint, AtopileError> {
+    let text = text.trim();
+    if let Some(name) = text.strip_prefix("signal ") {
+        return Ok(AtoEndpoint::Signal(name.trim().to_string()));
+    }
+    if let Some(name) = text.strip_prefix("pin ") {
+        return Ok(AtoEndpoint::Pin(name.trim().to_string()));
+    }
+    Ok(AtoEndpoint::Path(parse_path(file, line, text)?))
+}
+
+/// Parse a single statement, returning `None` for statements which don't
+/// affect the netlist.
+fn parse_statement(
+    file: &str,
+    line: usize,
+    text: &str,
+) -> Result<Option<AtoStatementKind>, AtopileError> {
+    let text = text.trim();
+    if text.is_empty() || text == "pass" || text.starts_with("assert ") {
+        return Ok(None);
+    }
+
+    if let Some(i) = find_unquoted(text, "~") {
+        let left = parse_endpoint(file, line, &text[..i])?;
+        let right = parse_endpoint(file, line, &text[i + 1..])?;
+        return Ok(Some(AtoStatementKind::Connect(left, right)));
+    }
+
+    if let Some(i) = find_unquoted(text, "=") {
+        let target = &text[..i];
+        // Skip comparisons and augmented assignments.
+        if text[i..].starts_with("==") || target.ends_with(['+', '-', '<', '>', '!']) {
+            return Ok(None);
+        }
+
+        // Drop type annotations, e.g. `resistance: resistance = 10kohm`.
+        let target = target.split(':').next().unwrap_or(target);
+        let value = text[i + 1..].trim();
+
+        if let Some(symbol) = value.strip_prefix("new ") {
+            let name = target.trim();
+            if !is_identifier(name) {
+                return Err(parse_error(
+                    file,
+                    line,
+                    &format!("Invalid instance name \"{}\"", name),
+                ));
+            }
+            return Ok(Some(AtoStatementKind::New {
+                name: name.to_string(),
+                symbol: symbol.trim().to_string(),
+            }));
+        }
+
+        return Ok(Some(AtoStatementKind::Assign {
+            target: parse_path(file, line, target)?,
+            value: unquote(value),
+        }));
+    }
+
+    if let Some(name) = text.strip_prefix("signal ") {
+        return Ok(Some(AtoStatementKind::Signal(name.trim().to_string())));
+    }
+    if let Some(name) = text.strip_prefix("pin ") {
+        return Ok(Some(AtoStatementKind::Pin(name.trim().to_string())));
+    }
+
+    // Declarations, specializations (`a -> B`) and anything else we don't
+    // model.
+    Ok(None)
+}
+
+fn parse_import(file: &str, line: usize, text: &str) -> Result<AtoImport, AtopileError> {
+    // `from "path" import A, B`, or the older `import A from "path"`.
+    let (path, symbols) = if let Some(rest) = text.strip_prefix("from ") {
+        let i = find_unquoted(rest, " import ")
+            .ok_or_else(|| parse_error(file, line, "Expected `import` after `from`"))?;
+        (&rest[..i], &rest[i + " import ".len()..])
+    } else {
+        let rest = text.strip_prefix("import ").unwrap_or(text);
+        let i = find_unquoted(rest, " from ")
+            .ok_or_else(|| parse_error(file, line, "Expected `from` after `import`"))?;
+        (&rest[i + " from ".len()..], &rest[..i])
+    };
+
+    Ok(AtoImport {
+        path: unquote(path),
+        symbols: symbols
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    })
+}
+
+fn parse_block_header(file: &str, line: usize, text: &str) -> Result<AtoBlock, AtopileError> {
+    let (keyword, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let kind = match keyword {
+        "component" => AtoBlockKind::Component,
+        "module" => AtoBlockKind::Module,
+        "interface" => AtoBlockKind::Interface,
+        _ => unreachable!("not a block header"),
+    };
+
+    let (name, parent) = match rest.split_once(" from ") {
+        Some((name, parent)) => (name.trim(), Some(parent.trim().to_string())),
+        None => (rest.trim(), None),
+    };
+    if !is_identifier(name) {
+        return Err(parse_error(
+            file,
+            line,
+            &format!("Invalid block name \"{}\"", name),
+        ));
+    }
+
+    Ok(AtoBlock {
+        line,
+        kind,
+        name: name.to_string(),
+        parent,
+        statements: vec![],
+    })
+}
+
+/// Parse the contents of an `.ato` file. `file` is only used in error
+/// messages.
+pub fn parse(file: &str, source: &str) -> Result<AtoFile, AtopileError> {
+    let mut ato_file = AtoFile {
+        imports: vec![],
+        blocks: vec![],
+    };
+    let mut in_docstring = false;
+
+    for (i, raw_line) in source.lines().enumerate() {
+        let line = i + 1;
+
+        // Skip docstrings, which may span several lines.
+        let trimmed = raw_line.trim();
+        if in_docstring {
+            in_docstring = !trimmed.contains("\"\"\"");
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("\"\"\"") {
+            in_docstring = !rest.contains("\"\"\"");
+            continue;
+        }
+
+        let text = strip_comment(raw_line);
+        if text.trim().is_empty() {
+            continue;
+        }
+        let comment = raw_line[text.len()..]
+            .strip_prefix('#')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty());
+
+        let indented = text.starts_with([' ', '\t']);
+        for statement in split_unquoted(text, ";") {
+            let statement = statement.trim();
+            if statement.is_empty() {
+                continue;
+            }
+
+            if indented {
+                let block = ato_file.blocks.last_mut().ok_or_else(|| {
+                    parse_error(file, line, "Unexpected indentation outside of a block")
+                })?;
+                if let Some(kind) = parse_statement(file, line, statement)? {
+                    block.statements.push(AtoStatement {
+                        line,
+                        kind,
+                        comment: comment.clone(),
+                    });
+                }
+                continue;
+            }
+
+            let keyword = statement.split_whitespace().next().unwrap_or("");
+            match keyword {
+                "from" | "import" => {
+                    ato_file.imports.push(parse_import(file, line, statement)?);
+                }
+                "component" | "module" | "interface" => {
+                    // The body may start on the same line, e.g. `module A: pass`.
+                    let (header, body) = statement
+                        .split_once(':')
+                        .ok_or_else(|| parse_error(file, line, "Expected `:` after block"))?;
+                    let mut block = parse_block_header(file, line, header.trim())?;
+                    if let Some(kind) = parse_statement(file, line, body)? {
+                        block.statements.push(AtoStatement {
+                            line,
+                            kind,
+                            comment: comment.clone(),
+                        });
+                    }
+                    ato_file.blocks.push(block);
+                }
+                _ => {
+                    return Err(parse_error(
+                        file,
+                        line,
+                        &format!("Unexpected statement \"{}\"", statement),
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(ato_file)
+}
@@ -0,0 +1,46 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{write_file, AtopileError, AtopileProject};
+
+const ATO_YAML_TEMPLATE: &str = include_str!("templates/ato.yaml");
+const GITIGNORE_TEMPLATE: &str = include_str!("templates/gitignore");
+
+/// An empty file that keeps a directory in git.
+const GITKEEP: &str = ".gitkeep";
+
+impl AtopileProject {
+    /// The files of a minimal Atopile project to generate this project into,
+    /// keyed by their path relative to the project directory: an `ato.yaml`
+    /// whose default build is the root module, a `.gitignore`, and the layout
+    /// directory. The source directory is created by generating the project.
+    pub fn scaffold_files(&self) -> BTreeMap<PathBuf, String> {
+        BTreeMap::from([
+            (
+                PathBuf::from("ato.yaml"),
+                ATO_YAML_TEMPLATE.replace("{entry}", &self.entry()),
+            ),
+            (PathBuf::from(".gitignore"), GITIGNORE_TEMPLATE.to_string()),
+            (
+                Path::new("elec").join("layout").join(GITKEEP),
+                String::new(),
+            ),
+        ])
+    }
+
+    /// Write the files of `scaffold_files` that don't exist yet to
+    /// `project_dir`, returning the ones that were written.
+    pub fn scaffold(&self, project_dir: &Path) -> Result<Vec<PathBuf>, AtopileError> {
+        let mut written = vec![];
+        for (path, contents) in self.scaffold_files() {
+            let target = project_dir.join(&path);
+            if !target.exists() {
+                write_file(&target, &contents)?;
+                written.push(path);
+            }
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use atopile::{schematics_from_atopile_module, AtopileProject};
+use schematics::{
+    component::{
+        ComponentBuilder, KICAD_SHEET_FILE_KEY, KICAD_SHEET_NAME_KEY, KICAD_SHEET_PATH_KEY,
+        KICAD_TSTAMP_KEY,
+    },
+    diff::{ComponentChange, NetChange},
+    net::NetBuilder,
+    Schematic,
+};
+
+/// Place a component on the sheet at `sheet_path`, e.g. `/Power/Filter/`.
+fn add_component(schematic: &mut Schematic, name: &str, part: &str, sheet_path: &str) {
+    let part = schematic.get_part(part).unwrap();
+    let mut cb = ComponentBuilder::default();
+    cb.name(name.to_string());
+    cb.part(part);
+    cb.metadata(KICAD_SHEET_PATH_KEY, sheet_path);
+    if let Some(sheet_name) = sheet_path.trim_end_matches('/').rsplit('/').next() {
+        if !sheet_name.is_empty() {
+            cb.metadata(KICAD_SHEET_NAME_KEY, sheet_name);
+        }
+    }
+    if name.starts_with('D') {
+        // Both LEDs come from the same sheet file.
+        cb.metadata(KICAD_SHEET_FILE_KEY, "led.kicad_sch");
+        cb.metadata(KICAD_TSTAMP_KEY, "00000000-0000-0000-0000-000000000060");
+    }
+    schematic.add_component(cb.build().unwrap()).unwrap();
+}
+
+fn add_net(schematic: &mut Schematic, name: &str, pins: &[(&str, &str)]) {
+    schematic
+        .add_net(
+            NetBuilder::default()
+                .name(name.to_string())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for (component, pin) in pins {
+        schematic.connect(name, component, pin).unwrap();
+    }
+}
+
+#[test]
+fn test_nested_sheets_round_trip() {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    add_component(&mut schematic, "R1", "Resistor", "/");
+    add_component(&mut schematic, "R2", "Resistor", "/Power/");
+    add_component(&mut schematic, "C1", "Capacitor", "/Power/Filter/");
+    add_component(&mut schematic, "D1", "LED", "/Led1/");
+    add_component(&mut schematic, "D2", "LED", "/Led2/");
+
+    add_net(
+        &mut schematic,
+        "VCC",
+        &[("R1", "1"), ("R2", "1"), ("C1", "1")],
+    );
+    add_net(&mut schematic, "PowerOUT", &[("R2", "2"), ("C1", "2")]);
+    add_net(&mut schematic, "MID1", &[("R1", "2"), ("D1", "2")]);
+    add_net(&mut schematic, "MID2", &[("D2", "2")]);
+    add_net(&mut schematic, "GND", &[("D1", "1"), ("D2", "1")]);
+
+    let project = AtopileProject::from_schematic("Demo".to_string(), &schematic).unwrap();
+    let output_dir = std::env::temp_dir().join(format!("atopile-hierarchy-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&output_dir);
+    project.generate_to_directory(&output_dir).unwrap();
+
+    let src: PathBuf = output_dir.join("elec/src");
+    let power = std::fs::read_to_string(src.join("Power.ato")).unwrap();
+    assert!(power.contains("signal VCC\n    VCC ~ Filter.VCC\n    VCC ~ R2.p1"));
+    assert!(power.contains("signal OUT\n    OUT ~ Filter.OUT\n    OUT ~ R2.p2"));
+    let root = std::fs::read_to_string(src.join("demo.ato")).unwrap();
+    assert!(root.contains("VCC ~ Power.VCC"));
+    assert!(root.contains("GND ~ Led1.GND\n    GND ~ Led2.GND"));
+    assert!(root.contains("Led2.D1.designator = \"D2\""));
+
+    let imported = schematics_from_atopile_module(&src.join("demo.ato"), "Demo").unwrap();
+    std::fs::remove_dir_all(&output_dir).unwrap();
+
+    // The connectivity survives, even if nets inside modules are renamed and
+    // the types of nets are inferred.
+    let diff = schematic.diff(&imported);
+    assert!(diff
+        .components
+        .iter()
+        .all(|c| !matches!(c, ComponentChange::MovedNet { .. })));
+    assert!(diff.nets.iter().all(|n| match n {
+        NetChange::Renamed { similarity, .. } => *similarity == 1.0,
+        NetChange::NetTypeChanged { .. } => true,
+        _ => false,
+    }));
+}
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use atopile::schematics_from_atopile_project;
+use schematics::{diff::Connection, part::PinType, Schematic};
+
+fn net_connections(schematic: &Schematic, net: &str) -> Vec<String> {
+    let net = schematic.get_net(net).expect("net not found");
+    let mut connections: Vec<String> = net
+        .as_deref()
+        .connections
+        .iter()
+        .map(|(c, p)| Connection::new(c, p).to_string())
+        .collect();
+    connections.sort();
+    connections
+}
+
+#[test]
+fn test_import_project() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/blinky");
+    let schematic = schematics_from_atopile_project(&path).unwrap();
+
+    let mut components: Vec<String> = schematic
+        .components_iter()
+        .map(|c| c.as_deref().name.clone())
+        .collect();
+    components.sort();
+    assert_eq!(components, vec!["D1", "R1", "R2"]);
+
+    let d1 = schematic.get_component("D1").unwrap();
+    assert_eq!(d1.as_deref().sheet_name(), Some("Indicator"));
+    assert_eq!(d1.as_deref().sheet_path(), vec!["indicator"]);
+    let led = d1.as_deref().part.clone();
+    assert_eq!(led.as_deref().name, "LED");
+    assert_eq!(led.as_deref().get_port("2").unwrap().as_deref().signal, "A");
+    assert_eq!(
+        led.as_deref().get_port("2").unwrap().as_deref().pin_type,
+        PinType::Passive
+    );
+    assert_eq!(led.as_deref().metadata["MPN"], "LTST-C191KRKT");
+
+    let r1 = schematic.get_component("R1").unwrap();
+    assert_eq!(r1.as_deref().part.as_deref().name, "Resistor");
+    assert_eq!(r1.as_deref().metadata["Value"], "1k");
+    let r2 = schematic.get_component("R2").unwrap();
+    assert_eq!(r2.as_deref().sheet_name(), None);
+
+    let mut nets: Vec<String> = schematic.nets_iter().map(|n| n.name()).collect();
+    nets.sort();
+    assert_eq!(
+        nets,
+        vec!["Net-(D1-A)", "control", "power.gnd", "power.vcc"]
+    );
+
+    assert_eq!(net_connections(&schematic, "power.vcc"), vec!["R1.1"]);
+    assert_eq!(net_connections(&schematic, "power.gnd"), vec!["R2.2"]);
+    assert_eq!(net_connections(&schematic, "control"), vec!["D1.1", "R2.1"]);
+    assert_eq!(
+        net_connections(&schematic, "Net-(D1-A)"),
+        vec!["D1.2", "R1.2"]
+    );
+}
@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+
+use atopile::AtopileProject;
+use schematics::{
+    component::{ComponentBuilder, KICAD_SHEET_NAME_KEY, KICAD_SHEET_PATH_KEY},
+    Schematic,
+};
+
+fn project_with_resistor(value: &str) -> AtopileProject {
+    project_with_sheets(value, &[])
+}
+
+/// A resistor on the root sheet, and a capacitor on each of `sheets`.
+fn project_with_sheets(value: &str, sheets: &[&str]) -> AtopileProject {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    let resistor = schematic.get_part("Resistor").unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .metadata("Value", value)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for (i, sheet) in sheets.iter().enumerate() {
+        let capacitor = schematic.get_part("Capacitor").unwrap();
+        schematic
+            .add_component(
+                ComponentBuilder::default()
+                    .name(format!("C{}", i + 1))
+                    .part(capacitor)
+                    .metadata(KICAD_SHEET_PATH_KEY, &format!("/{}/", sheet))
+                    .metadata(KICAD_SHEET_NAME_KEY, sheet)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+    AtopileProject::from_schematic("Demo".to_string(), &schematic).unwrap()
+}
+
+fn temp_project_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("atopile-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn edit(path: &Path, from: &str, to: &str) {
+    let contents = std::fs::read_to_string(path).unwrap();
+    assert!(contents.contains(from), "{} not in {}", from, contents);
+    std::fs::write(path, contents.replace(from, to)).unwrap();
+}
+
+#[test]
+fn test_merge_keeps_edits() {
+    let dir = temp_project_dir("merge");
+    project_with_resistor("1k")
+        .generate_to_directory(&dir)
+        .unwrap();
+
+    let root = dir.join("elec/src/demo.ato");
+    edit(
+        &root,
+        "module Demo:\n",
+        "module Demo:\n    # Pull-up for the reset line\n",
+    );
+
+    let report = project_with_resistor("10k")
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert_eq!(report.updated, vec![PathBuf::from("elec/src/demo.ato")]);
+    assert!(!report.has_conflicts());
+    let merged = std::fs::read_to_string(&root).unwrap();
+    assert!(merged.contains("# Pull-up for the reset line"));
+    assert!(merged.contains("R1.value = \"10k\""));
+
+    // Editing a line the conversion also changes is a conflict.
+    edit(&root, "R1.value = \"10k\"", "R1.value = \"10K\"");
+    let report = project_with_resistor("4k7")
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert_eq!(report.conflicted, vec![PathBuf::from("elec/src/demo.ato")]);
+    let merged = std::fs::read_to_string(&root).unwrap();
+    assert!(merged.contains("<<<<<<< ours\n    R1.value = \"10K\""));
+    assert!(merged.contains("=======\n    R1.value = \"4k7\""));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_merge_without_base() {
+    let dir = temp_project_dir("merge-without-base");
+    project_with_resistor("1k")
+        .generate_to_directory(&dir)
+        .unwrap();
+    std::fs::remove_dir_all(dir.join(".diode")).unwrap();
+
+    // Blocks and imports added to a file are kept.
+    let root = dir.join("elec/src/demo.ato");
+    edit(
+        &root,
+        "module Demo:\n",
+        "from \"extra.ato\" import Extra\n\nmodule Demo:\n",
+    );
+    let contents = std::fs::read_to_string(&root).unwrap();
+    std::fs::write(
+        &root,
+        format!("{}\nmodule Spare:\n    signal spare\n", contents),
+    )
+    .unwrap();
+
+    let report = project_with_resistor("1k")
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert!(!report.has_conflicts());
+    let merged = std::fs::read_to_string(&root).unwrap();
+    assert!(merged.contains("from \"extra.ato\" import Extra\n"));
+    assert!(merged.contains("module Spare:\n    signal spare\n"));
+
+    // The merge saved a base, so the next one only updates what changed.
+    assert!(dir.join(".diode/base/elec/src/demo.ato").exists());
+    let report = project_with_resistor("10k")
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert_eq!(report.updated, vec![PathBuf::from("elec/src/demo.ato")]);
+    assert!(!report.has_conflicts());
+    let merged = std::fs::read_to_string(&root).unwrap();
+    assert!(merged.contains("R1.value = \"10k\""));
+    assert!(merged.contains("module Spare:"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_merge_without_base_conflicts() {
+    let dir = temp_project_dir("merge-without-base-conflicts");
+    project_with_resistor("1k")
+        .generate_to_directory(&dir)
+        .unwrap();
+    std::fs::remove_dir_all(dir.join(".diode")).unwrap();
+
+    let root = dir.join("elec/src/demo.ato");
+    edit(&root, "module Demo:\n", "module Demo:\n    # Pull-up\n");
+    let contents = std::fs::read_to_string(&root).unwrap();
+    std::fs::write(
+        &root,
+        format!("{}\nmodule Spare:\n    signal spare\n", contents),
+    )
+    .unwrap();
+
+    // Without a base, a block that differs on both sides is a conflict, but
+    // the rest of the file still merges.
+    let report = project_with_resistor("10k")
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert_eq!(report.conflicted, vec![PathBuf::from("elec/src/demo.ato")]);
+    let merged = std::fs::read_to_string(&root).unwrap();
+    assert!(merged.contains("<<<<<<< ours\nmodule Demo:\n    # Pull-up\n"));
+    assert!(merged.contains("=======\nmodule Demo:\n"));
+    assert!(merged.contains("R1.value = \"10k\"\n>>>>>>> theirs\n"));
+    assert!(merged.contains(">>>>>>> theirs\n\nmodule Spare:\n"));
+    assert_eq!(merged.matches("<<<<<<<").count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_merge_removed_files() {
+    let dir = temp_project_dir("merge-removed");
+    project_with_sheets("1k", &["Power", "Filter"])
+        .generate_to_directory(&dir)
+        .unwrap();
+    let power = dir.join("elec/src/Power.ato");
+    let filter = dir.join("elec/src/Filter.ato");
+    assert!(power.exists());
+    edit(
+        &filter,
+        "module Filter:\n",
+        "module Filter:\n    # Tuned by hand\n",
+    );
+
+    // Files no longer generated are removed, unless they were edited.
+    let report = project_with_sheets("1k", &[])
+        .merge_into_directory(&dir)
+        .unwrap();
+    assert_eq!(report.removed, vec![PathBuf::from("elec/src/Power.ato")]);
+    assert_eq!(report.kept, vec![PathBuf::from("elec/src/Filter.ato")]);
+    assert!(!power.exists());
+    assert!(filter.exists());
+    assert!(!dir.join(".diode/base/elec/src/Filter.ato").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
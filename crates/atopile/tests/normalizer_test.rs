@@ -0,0 +1,28 @@
+use atopile::AtopileNormalizer;
+use schematics::{net::NetBuilder, NormalizationError, Schematic, SchematicError};
+
+#[test]
+fn test_normalize_name_conflict() {
+    // `V+` is normalized to `VP`, so the two nets clash.
+    let mut schematic = Schematic::new();
+    for name in ["V+", "VP"] {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    let result = schematic.normalize(AtopileNormalizer::default());
+    assert!(
+        matches!(
+            result,
+            Err(SchematicError::NormalizationError(NormalizationError::NameConflict(ref name))) if name == "VP"
+        ),
+        "{:?}",
+        result
+    );
+}
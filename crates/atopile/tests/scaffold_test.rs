@@ -0,0 +1,44 @@
+use atopile::{schematics_from_atopile_module, AtopileProject};
+use schematics::{component::ComponentBuilder, Schematic};
+
+#[test]
+fn test_scaffold_project() {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    let resistor = schematic.get_part("Resistor").unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let project = AtopileProject::from_schematic("Demo".to_string(), &schematic).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("atopile-scaffold-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let written = project.scaffold(&dir).unwrap();
+    assert_eq!(written.len(), 3);
+    project.generate_to_directory(&dir).unwrap();
+
+    let ato_yaml = std::fs::read_to_string(dir.join("ato.yaml")).unwrap();
+    assert!(ato_yaml.contains("entry: elec/src/demo.ato:Demo"));
+    assert!(dir.join(".gitignore").is_file());
+    assert!(dir.join("elec/layout").is_dir());
+
+    // The entry point names the generated root module.
+    let imported = schematics_from_atopile_module(&dir.join("elec/src/demo.ato"), "Demo").unwrap();
+    assert!(imported.get_component("R1").is_some());
+
+    // Files that already exist are left alone.
+    std::fs::write(dir.join(".gitignore"), "build/\n").unwrap();
+    assert!(project.scaffold(&dir).unwrap().is_empty());
+    assert_eq!(
+        std::fs::read_to_string(dir.join(".gitignore")).unwrap(),
+        "build/\n"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,231 @@
+use schematics::{
+    part::PartRef,
+    standard_library::{
+        self, bjt, capacitor, connector, crystal, diode, ferrite_bead, inductor, led, mosfet,
+        mounting_hole, resistor, test_point,
+    },
+    Schematic,
+};
+
+/// KiCad's libraries of generic connectors, e.g. `Connector_Generic:Conn_01x04`.
+const CONNECTOR_LIBRARIES: &[&str] = &["Connector", "Connector_Generic"];
+
+/// A KiCad symbol which stands for a generic part from the standard library.
+struct GenericSymbol {
+    lib_id: &'static str,
+    part: &'static str,
+}
+
+const GENERIC_SYMBOLS: &[GenericSymbol] = &[
+    GenericSymbol {
+        lib_id: "Device:R",
+        part: resistor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:R_Small",
+        part: resistor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:R_US",
+        part: resistor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:R_Small_US",
+        part: resistor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:C",
+        part: capacitor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:C_Small",
+        part: capacitor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:L",
+        part: inductor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:L_Small",
+        part: inductor::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:FerriteBead",
+        part: ferrite_bead::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:FerriteBead_Small",
+        part: ferrite_bead::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D_Small",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D_Schottky",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D_Schottky_Small",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D_Zener",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:D_Zener_Small",
+        part: diode::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:LED",
+        part: led::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:LED_Small",
+        part: led::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Q_NPN_BEC",
+        part: bjt::NPN_NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Q_PNP_BEC",
+        part: bjt::PNP_NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Q_NMOS_GSD",
+        part: mosfet::NMOS_NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Q_PMOS_GSD",
+        part: mosfet::PMOS_NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Crystal",
+        part: crystal::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Device:Crystal_Small",
+        part: crystal::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Connector:TestPoint",
+        part: test_point::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Connector:TestPoint_Small",
+        part: test_point::NAME,
+    },
+    GenericSymbol {
+        lib_id: "Mechanical:MountingHole",
+        part: mounting_hole::NAME,
+    },
+];
+
+/// Metadata recorded on the components of generic symbols which are more
+/// specific than their standard library part, e.g. a Schottky diode.
+const SYMBOL_METADATA: &[(&str, &str, &str)] = &[
+    ("Device:D_Schottky", diode::DIODE_TYPE_KEY, diode::SCHOTTKY),
+    (
+        "Device:D_Schottky_Small",
+        diode::DIODE_TYPE_KEY,
+        diode::SCHOTTKY,
+    ),
+    ("Device:D_Zener", diode::DIODE_TYPE_KEY, diode::ZENER),
+    ("Device:D_Zener_Small", diode::DIODE_TYPE_KEY, diode::ZENER),
+];
+
+/// The part a KiCad symbol is imported as.
+#[derive(Clone)]
+pub struct SymbolPart {
+    pub part: PartRef,
+    /// The metadata key the component's value is stored under, for generic
+    /// parts.
+    pub value_key: Option<&'static str>,
+    /// Metadata to record on each component, for generic parts.
+    pub metadata: Vec<(&'static str, &'static str)>,
+}
+
+/// The number of rows and pins per row of a generic connector symbol, e.g.
+/// `Connector_Generic:Conn_02x05_Odd_Even` or `Connector:Conn_01x04_Pin`.
+fn connector_size(lib_id: &str) -> Option<(usize, usize)> {
+    let (library, name) = lib_id.split_once(':')?;
+    if !CONNECTOR_LIBRARIES.contains(&library) {
+        return None;
+    }
+    let (rows, rest) = name.strip_prefix("Conn_")?.split_once('x')?;
+    let pins_per_row: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    Some((rows.parse().ok()?, pins_per_row.parse().ok()?))
+}
+
+/// Recognise a KiCad symbol (e.g. `Device:R`) as a generic part from the
+/// standard library, registering it first for connectors. The symbol's pins
+/// must be numbered like the part's ports, so that modified copies of the
+/// symbols aren't matched.
+pub fn generic_part(schematic: &mut Schematic, lib_id: &str, pins: &[&str]) -> Option<SymbolPart> {
+    let (part, value_key) = match GENERIC_SYMBOLS.iter().find(|s| s.lib_id == lib_id) {
+        Some(symbol) => (
+            schematic.get_part(symbol.part)?,
+            standard_library::value_key(symbol.part),
+        ),
+        None => {
+            let (rows, pins_per_row) = connector_size(lib_id)?;
+            let count = rows * pins_per_row;
+            let numbered =
+                pins.len() == count && (1..=count).all(|i| pins.contains(&i.to_string().as_str()));
+            if !numbered {
+                return None;
+            }
+            let part = connector::get_or_register(schematic, rows, pins_per_row).ok()?;
+            (part, None)
+        }
+    };
+
+    let matches = {
+        let part = part.as_deref();
+        pins.len() == part.ports_by_terminal_identifier.len()
+            && pins
+                .iter()
+                .all(|p| part.ports_by_terminal_identifier.contains_key(*p))
+    };
+    let metadata = SYMBOL_METADATA
+        .iter()
+        .filter(|(id, _, _)| *id == lib_id)
+        .map(|(_, key, value)| (*key, *value))
+        .collect();
+    matches.then_some(SymbolPart {
+        part,
+        value_key,
+        metadata,
+    })
+}
+
+#[test]
+fn test_connector_size() {
+    assert_eq!(connector_size("Connector_Generic:Conn_01x04"), Some((1, 4)));
+    assert_eq!(
+        connector_size("Connector_Generic:Conn_02x05_Odd_Even"),
+        Some((2, 5))
+    );
+    assert_eq!(connector_size("Connector:Conn_01x02_Pin"), Some((1, 2)));
+    assert_eq!(connector_size("Connector:USB_C_Receptacle"), None);
+    assert_eq!(connector_size("MyLib:Conn_01x04"), None);
+}
+
+#[test]
+fn test_diode_type() {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+
+    let zener = generic_part(&mut schematic, "Device:D_Zener_Small", &["1", "2"]).unwrap();
+    assert_eq!(zener.part.as_deref().name, diode::NAME);
+    assert_eq!(zener.metadata, vec![(diode::DIODE_TYPE_KEY, diode::ZENER)]);
+
+    let diode = generic_part(&mut schematic, "Device:D", &["1", "2"]).unwrap();
+    assert!(diode.metadata.is_empty());
+}
@@ -1,9 +1,26 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use generics::{generic_part, SymbolPart};
 use kicad_format::{parse_netlist_file, KiCadParseError};
 use schematics::{
-    component::ComponentBuilder, net::NetBuilder, part::PartBuilder, Schematic, SchematicError,
+    component::{ComponentBuilder, KICAD_SHEET_PATH_KEY, KICAD_SHEET_TSTAMPS_KEY},
+    net::NetBuilder,
+    net_inference::NetTypeOverrides,
+    part::{PartBuilder, PinType},
+    value::normalize,
+    Schematic, SchematicError,
 };
 use thiserror::Error;
 
+mod generics;
+mod schematic;
+mod sexpr;
+
+pub use schematic::schematics_from_kicad_schematic;
+
+/// The KiCad library containing power symbols.
+const KICAD_POWER_LIBRARY: &str = "power";
+
 #[derive(Error, Debug)]
 pub enum SchematicImportError {
     #[error("Failed to parse Kicad file: {0}")]
@@ -12,6 +29,8 @@ pub enum SchematicImportError {
     SchematicError(#[from] SchematicError),
     #[error("Failed to interpret Kicad netlist: {0}")]
     InterpretationError(String),
+    #[error("Failed to read {0}: {1}")]
+    ReadError(PathBuf, std::io::Error),
 }
 
 /// Import a Kicad netlist file into a Schematic.
@@ -21,21 +40,38 @@ pub fn schematics_from_kicad_netlist(file: &str) -> Result<Schematic, SchematicI
 
     let netlist = parse_netlist_file(file)?;
 
-    // Register a Part for each library part.
+    // Register a Part for each library part, except for generic parts which
+    // use the standard library instead.
+    let mut generic_parts: HashMap<String, SymbolPart> = HashMap::new();
     for netlist_part in netlist.libparts.iter() {
-        let pins: Vec<(String, String)> = netlist_part
+        let lib_id = format!("{}:{}", netlist_part.lib, netlist_part.part);
+        let pin_numbers: Vec<&str> = netlist_part
+            .pins
+            .iter()
+            .flatten()
+            .map(|p| p.num.as_str())
+            .collect();
+        if let Some(generic) = generic_part(&mut schematic, &lib_id, &pin_numbers) {
+            generic_parts.insert(lib_id, generic);
+            continue;
+        }
+
+        let pins: Vec<(String, String, PinType)> = netlist_part
             .pins
             .as_ref()
             .unwrap_or(&[].to_vec())
             .iter()
-            .map(|p| (p.num.clone(), p.name.clone()))
+            .map(|p| {
+                let pin_type = p.type_.parse().unwrap_or_default();
+                (p.num.clone(), p.name.clone(), pin_type)
+            })
             .collect();
 
         let mut pb = PartBuilder::default();
         pb.name(netlist_part.part.clone());
 
-        for (num, name) in pins {
-            pb.port(num.as_str(), name.as_str());
+        for (num, name, pin_type) in pins {
+            pb.port_with_type(num.as_str(), name.as_str(), pin_type);
         }
 
         for field in netlist_part.fields.iter() {
@@ -43,6 +79,7 @@ pub fn schematics_from_kicad_netlist(file: &str) -> Result<Schematic, SchematicI
         }
 
         let part = pb.build()?;
+        schematic.unregister_standard_part(&part.name);
         schematic.add_part(part)?;
     }
 
@@ -51,17 +88,30 @@ pub fn schematics_from_kicad_netlist(file: &str) -> Result<Schematic, SchematicI
         let mut cb = ComponentBuilder::default();
         cb.name(netlist_component.ref_.clone());
 
-        let partname = netlist_component.libsource.part.clone();
+        let libsource = &netlist_component.libsource;
+        let generic = generic_parts.get(&format!("{}:{}", libsource.lib, libsource.part));
+
+        let partname = libsource.part.clone();
         let part =
-            schematic
-                .get_part(&partname)
-                .ok_or(SchematicImportError::InterpretationError(format!(
-                    "Part {} not found",
-                    partname
-                )))?;
+            match generic {
+                Some(generic) => generic.part.clone(),
+                None => schematic.get_part(&partname).ok_or(
+                    SchematicImportError::InterpretationError(format!(
+                        "Part {} not found",
+                        partname
+                    )),
+                )?,
+            };
 
         cb.part(part.clone());
 
+        if let Some(value_key) = generic.and_then(|g| g.value_key) {
+            cb.metadata(value_key, &normalize(value_key, &netlist_component.value));
+        }
+        for (key, value) in generic.iter().flat_map(|g| g.metadata.iter()) {
+            cb.metadata(key, value);
+        }
+
         for property in netlist_component.properties.iter() {
             cb.metadata(
                 property.name.as_str(),
@@ -69,14 +119,40 @@ pub fn schematics_from_kicad_netlist(file: &str) -> Result<Schematic, SchematicI
             );
         }
 
+        let sheetpath = &netlist_component.sheetpath;
+        if !sheetpath.names.is_empty() {
+            cb.metadata(KICAD_SHEET_PATH_KEY, &sheetpath.names);
+        }
+        if !sheetpath.tstamps.is_empty() {
+            cb.metadata(KICAD_SHEET_TSTAMPS_KEY, &sheetpath.tstamps);
+        }
+
         let component = cb.build()?;
         schematic.add_component(component)?;
     }
 
+    // Power symbols are usually left out of netlists, but when they are
+    // included they tell us the type of the net they're connected to.
+    let power_symbols: HashMap<&str, String> = netlist
+        .components
+        .iter()
+        .filter(|c| c.libsource.lib == KICAD_POWER_LIBRARY)
+        .map(|c| {
+            let symbol = format!("{}:{}", c.libsource.lib, c.libsource.part);
+            (c.ref_.as_str(), symbol)
+        })
+        .collect();
+
     // Register a Net for each net in the netlist.
     for netlist_net in netlist.nets.iter() {
         let mut nb = NetBuilder::default();
         nb.name(netlist_net.name.clone());
+        nb.power_symbol(
+            netlist_net
+                .nodes
+                .iter()
+                .find_map(|n| power_symbols.get(n.ref_.as_str()).cloned()),
+        );
         let net = nb.build()?;
         let net = schematic.add_net(net)?;
 
@@ -98,5 +174,7 @@ pub fn schematics_from_kicad_netlist(file: &str) -> Result<Schematic, SchematicI
         }
     }
 
+    schematic.infer_net_types(&NetTypeOverrides::default())?;
+
     Ok(schematic)
 }
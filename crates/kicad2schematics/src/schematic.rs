@@ -0,0 +1,812 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use natord::compare;
+use schematics::{
+    component::{
+        ComponentBuilder, KICAD_SHEET_FILE_KEY, KICAD_SHEET_NAME_KEY, KICAD_SHEET_PATH_KEY,
+        KICAD_SHEET_TSTAMPS_KEY, KICAD_TSTAMP_KEY,
+    },
+    net::NetBuilder,
+    net_inference::NetTypeOverrides,
+    part::{PartBuilder, PinType},
+    value::normalize,
+    Schematic,
+};
+
+use crate::{
+    generics::{generic_part, SymbolPart},
+    sexpr::{self, SExpr},
+    SchematicImportError,
+};
+
+/// Hierarchies deeper than this are assumed to be recursive.
+const MAX_SHEET_DEPTH: usize = 32;
+
+/// A position on a sheet, in units of 1/10000 mm so that coordinates can be
+/// compared exactly.
+type Point = (i64, i64);
+
+fn to_units(mm: f64) -> i64 {
+    (mm * 10000.0).round() as i64
+}
+
+fn position(expr: &SExpr) -> Option<(Point, f64)> {
+    let at = expr.child("at")?;
+    let point = (to_units(at.number(1)?), to_units(at.number(2)?));
+    Some((point, at.number(3).unwrap_or(0.0)))
+}
+
+fn property<'a>(expr: &'a SExpr, name: &str) -> Option<&'a str> {
+    expr.children("property")
+        .find(|p| p.atom(1) == Some(name))
+        .and_then(|p| p.atom(2))
+}
+
+fn is_on_segment(point: Point, a: Point, b: Point) -> bool {
+    let cross = (b.0 - a.0) as i128 * (point.1 - a.1) as i128
+        - (b.1 - a.1) as i128 * (point.0 - a.0) as i128;
+    cross == 0
+        && point.0 >= a.0.min(b.0)
+        && point.0 <= a.0.max(b.0)
+        && point.1 >= a.1.min(b.1)
+        && point.1 <= a.1.max(b.1)
+}
+
+/// One use of a sheet in the hierarchy. A sheet file that is instantiated
+/// several times has one `SheetInstance` per instantiation.
+struct SheetInstance {
+    document: Rc<SExpr>,
+
+    /// The UUIDs of the sheet symbols leading to this instance, starting
+    /// below the root.
+    uuid_path: Vec<String>,
+
+    /// The human-readable path of the instance, e.g. `/Power/`.
+    name_path: String,
+
+    sheet_name: Option<String>,
+    sheet_file: Option<String>,
+
+    /// The index of the parent instance, and the hierarchical pins on the
+    /// sheet symbol in the parent, which connect to the hierarchical labels of
+    /// this instance.
+    parent: Option<(usize, Vec<(String, Point)>)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LabelKind {
+    Power,
+    Global,
+    Local,
+    Hierarchical,
+}
+
+struct Label {
+    kind: LabelKind,
+    name: String,
+    node: usize,
+    instance: usize,
+
+    /// The library symbol of a power symbol, e.g. `power:GND`.
+    power_symbol: Option<String>,
+}
+
+struct Pin {
+    reference: String,
+    number: String,
+    name: String,
+    node: usize,
+}
+
+/// A component, which may be placed as several units.
+struct PlacedComponent {
+    lib_key: String,
+    document: Rc<SExpr>,
+    metadata: BTreeMap<String, String>,
+}
+
+/// Union-find over the connection points of every sheet instance.
+#[derive(Default)]
+struct Connectivity {
+    parents: Vec<usize>,
+    nodes: HashMap<(usize, Point), usize>,
+}
+
+impl Connectivity {
+    fn node(&mut self, instance: usize, point: Point) -> usize {
+        let next = self.parents.len();
+        let node = *self.nodes.entry((instance, point)).or_insert(next);
+        if node == next {
+            self.parents.push(next);
+        }
+        node
+    }
+
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut node = node;
+        while self.parents[node] != root {
+            let next = self.parents[node];
+            self.parents[node] = root;
+            node = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a != b {
+            self.parents[a] = b;
+        }
+    }
+}
+
+struct SchematicReader {
+    documents: HashMap<PathBuf, Rc<SExpr>>,
+    instances: Vec<SheetInstance>,
+    root_uuid: String,
+
+    /// KiCad 6 records the reference and unit of every symbol instance in the
+    /// root sheet, keyed by the path of sheet and symbol UUIDs.
+    legacy_references: HashMap<String, (String, Option<i64>)>,
+
+    connectivity: Connectivity,
+    labels: Vec<Label>,
+    pins: Vec<Pin>,
+    components: BTreeMap<String, PlacedComponent>,
+}
+
+impl SchematicReader {
+    fn load(&mut self, path: &Path) -> Result<Rc<SExpr>, SchematicImportError> {
+        if let Some(document) = self.documents.get(path) {
+            return Ok(document.clone());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| SchematicImportError::ReadError(path.to_path_buf(), e))?;
+        let document = Rc::new(sexpr::parse(&contents)?);
+        if document.head() != Some("kicad_sch") {
+            return Err(SchematicImportError::InterpretationError(format!(
+                "{} is not a KiCad schematic",
+                path.display()
+            )));
+        }
+
+        self.documents.insert(path.to_path_buf(), document.clone());
+        Ok(document)
+    }
+
+    /// Walk the sheet hierarchy, creating an instance for every sheet.
+    fn add_instances(
+        &mut self,
+        path: &Path,
+        instance: SheetInstance,
+    ) -> Result<(), SchematicImportError> {
+        if instance.uuid_path.len() > MAX_SHEET_DEPTH {
+            return Err(SchematicImportError::InterpretationError(format!(
+                "Sheet hierarchy is too deep at {}, is a sheet recursive?",
+                instance.name_path
+            )));
+        }
+
+        let document = instance.document.clone();
+        let index = self.instances.len();
+        let name_path = instance.name_path.clone();
+        let uuid_path = instance.uuid_path.clone();
+        self.instances.push(instance);
+
+        for sheet in document.children("sheet") {
+            let sheet_name = property(sheet, "Sheetname")
+                .or_else(|| property(sheet, "Sheet name"))
+                .unwrap_or("")
+                .to_string();
+            let sheet_file = property(sheet, "Sheetfile")
+                .or_else(|| property(sheet, "Sheet file"))
+                .ok_or(SchematicImportError::InterpretationError(format!(
+                    "Sheet {} in {} has no file",
+                    sheet_name,
+                    path.display()
+                )))?
+                .to_string();
+            let uuid = sheet
+                .child("uuid")
+                .and_then(|u| u.atom(1))
+                .unwrap_or("")
+                .to_string();
+
+            let pins = sheet
+                .children("pin")
+                .filter_map(|pin| Some((pin.atom(1)?.to_string(), position(pin)?.0)))
+                .collect();
+
+            let child_path = path.parent().unwrap_or(Path::new(".")).join(&sheet_file);
+            let child_document = self.load(&child_path)?;
+
+            let mut child_uuid_path = uuid_path.clone();
+            child_uuid_path.push(uuid);
+
+            self.add_instances(
+                &child_path,
+                SheetInstance {
+                    document: child_document,
+                    uuid_path: child_uuid_path,
+                    name_path: format!("{}{}/", name_path, sheet_name),
+                    sheet_name: Some(sheet_name),
+                    sheet_file: Some(sheet_file),
+                    parent: Some((index, pins)),
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the reference and unit of a symbol in a given sheet instance.
+    fn symbol_reference(&self, instance: &SheetInstance, symbol: &SExpr) -> (String, i64) {
+        let fallback_unit = symbol
+            .child("unit")
+            .and_then(|u| u.number(1))
+            .unwrap_or(1.0) as i64;
+        let fallback_reference = property(symbol, "Reference").unwrap_or("").to_string();
+
+        // KiCad 7 and later store the instances on the symbol itself.
+        let instance_path = format!(
+            "/{}",
+            std::iter::once(self.root_uuid.as_str())
+                .chain(instance.uuid_path.iter().map(|u| u.as_str()))
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+        let paths: Vec<&SExpr> = symbol
+            .child("instances")
+            .into_iter()
+            .flat_map(|i| i.children("project"))
+            .flat_map(|p| p.children("path"))
+            .collect();
+        let path = paths
+            .iter()
+            .find(|p| p.atom(1) == Some(instance_path.as_str()))
+            .or(if paths.len() == 1 {
+                paths.first()
+            } else {
+                None
+            });
+        if let Some(path) = path {
+            let reference = path.child("reference").and_then(|r| r.atom(1));
+            let unit = path.child("unit").and_then(|u| u.number(1));
+            if let Some(reference) = reference {
+                return (
+                    reference.to_string(),
+                    unit.map(|u| u as i64).unwrap_or(fallback_unit),
+                );
+            }
+        }
+
+        // KiCad 6 stores them in the root sheet.
+        let uuid = symbol.child("uuid").and_then(|u| u.atom(1)).unwrap_or("");
+        let legacy_path = instance
+            .uuid_path
+            .iter()
+            .map(|u| format!("/{}", u))
+            .collect::<String>()
+            + "/"
+            + uuid;
+        if let Some((reference, unit)) = self.legacy_references.get(&legacy_path) {
+            return (reference.clone(), unit.unwrap_or(fallback_unit));
+        }
+
+        (fallback_reference, fallback_unit)
+    }
+
+    fn add_connectivity(&mut self, index: usize) {
+        let document = self.instances[index].document.clone();
+        let mut points: HashSet<Point> = HashSet::new();
+        let mut wires: Vec<(Point, Point)> = vec![];
+
+        for wire in document.children("wire") {
+            let xys: Vec<Point> = wire
+                .child("pts")
+                .into_iter()
+                .flat_map(|pts| pts.children("xy"))
+                .filter_map(|xy| Some((to_units(xy.number(1)?), to_units(xy.number(2)?))))
+                .collect();
+            for segment in xys.windows(2) {
+                let a = self.connectivity.node(index, segment[0]);
+                let b = self.connectivity.node(index, segment[1]);
+                self.connectivity.union(a, b);
+                points.insert(segment[0]);
+                points.insert(segment[1]);
+                wires.push((segment[0], segment[1]));
+            }
+        }
+
+        for (head, kind) in [
+            ("label", LabelKind::Local),
+            ("global_label", LabelKind::Global),
+            ("hierarchical_label", LabelKind::Hierarchical),
+        ] {
+            for label in document.children(head) {
+                let (Some(name), Some((point, _))) = (label.atom(1), position(label)) else {
+                    continue;
+                };
+                points.insert(point);
+                let node = self.connectivity.node(index, point);
+                self.labels.push(Label {
+                    kind,
+                    name: name.to_string(),
+                    node,
+                    instance: index,
+                    power_symbol: None,
+                });
+            }
+        }
+
+        for sheet in document.children("sheet") {
+            for pin in sheet.children("pin") {
+                if let Some((point, _)) = position(pin) {
+                    points.insert(point);
+                    self.connectivity.node(index, point);
+                }
+            }
+        }
+
+        let lib_symbols = document.child("lib_symbols");
+        for symbol in document.children("symbol") {
+            let Some(lib_id) = symbol.child("lib_id").and_then(|l| l.atom(1)) else {
+                continue;
+            };
+            let lib_key = symbol
+                .child("lib_name")
+                .and_then(|l| l.atom(1))
+                .unwrap_or(lib_id);
+            let Some(lib_symbol) = lib_symbols
+                .into_iter()
+                .flat_map(|l| l.children("symbol"))
+                .find(|s| s.atom(1) == Some(lib_key))
+            else {
+                continue;
+            };
+
+            let (reference, unit) = self.symbol_reference(&self.instances[index], symbol);
+            let is_power = lib_symbol.child("power").is_some();
+
+            let Some((origin, angle)) = position(symbol) else {
+                continue;
+            };
+            let mirror = symbol.child("mirror").and_then(|m| m.atom(1));
+
+            for pin in unit_pins(lib_symbol, unit) {
+                let point = transform(origin, angle, mirror, pin.position);
+                points.insert(point);
+                let node = self.connectivity.node(index, point);
+
+                if is_power || pin.implicit_power {
+                    // Power symbols connect their pin to a global net named
+                    // after their value.
+                    let name = match is_power {
+                        true => property(symbol, "Value").unwrap_or(&pin.name),
+                        false => &pin.name,
+                    };
+                    self.labels.push(Label {
+                        kind: LabelKind::Power,
+                        name: name.to_string(),
+                        node,
+                        instance: index,
+                        power_symbol: is_power.then(|| lib_id.to_string()),
+                    });
+                }
+                if !is_power && !reference.starts_with('#') {
+                    self.pins.push(Pin {
+                        reference: reference.clone(),
+                        number: pin.number,
+                        name: pin.name,
+                        node,
+                    });
+                }
+            }
+
+            // Power flags, logos and other references starting with `#` are
+            // not real components.
+            if is_power || reference.starts_with('#') || self.components.contains_key(&reference) {
+                continue;
+            }
+
+            let instance = &self.instances[index];
+            let mut metadata: BTreeMap<String, String> = symbol
+                .children("property")
+                .filter_map(|p| Some((p.atom(1)?.to_string(), p.atom(2)?.to_string())))
+                .filter(|(name, _)| name != "Reference")
+                .collect();
+            if let Some(sheet_name) = instance.sheet_name.as_ref() {
+                metadata.insert(KICAD_SHEET_NAME_KEY.to_string(), sheet_name.clone());
+            }
+            if let Some(sheet_file) = instance.sheet_file.as_ref() {
+                metadata.insert(KICAD_SHEET_FILE_KEY.to_string(), sheet_file.clone());
+            }
+            metadata.insert(KICAD_SHEET_PATH_KEY.to_string(), instance.name_path.clone());
+            let tstamps: String = instance
+                .uuid_path
+                .iter()
+                .map(|u| format!("/{}", u))
+                .collect();
+            metadata.insert(KICAD_SHEET_TSTAMPS_KEY.to_string(), tstamps + "/");
+            if let Some(uuid) = symbol.child("uuid").and_then(|u| u.atom(1)) {
+                metadata.insert(KICAD_TSTAMP_KEY.to_string(), uuid.to_string());
+            }
+
+            self.components.insert(
+                reference,
+                PlacedComponent {
+                    lib_key: lib_key.to_string(),
+                    document: document.clone(),
+                    metadata,
+                },
+            );
+        }
+
+        // A point touching the middle of a wire is connected to it.
+        for (a, b) in wires.iter() {
+            let wire_node = self.connectivity.node(index, *a);
+            for point in points.iter() {
+                if is_on_segment(*point, *a, *b) {
+                    let node = self.connectivity.node(index, *point);
+                    self.connectivity.union(node, wire_node);
+                }
+            }
+        }
+    }
+
+    /// Connect labels with the same name, and hierarchical labels to the
+    /// sheet pins in their parent. Local and hierarchical labels share the
+    /// scope of their sheet.
+    fn connect_labels(&mut self) {
+        let mut by_name: HashMap<(Option<usize>, &str), usize> = HashMap::new();
+        for label in self.labels.iter() {
+            let scope = match label.kind {
+                LabelKind::Power | LabelKind::Global => None,
+                LabelKind::Local | LabelKind::Hierarchical => Some(label.instance),
+            };
+            match by_name.get(&(scope, label.name.as_str())) {
+                Some(node) => self.connectivity.union(*node, label.node),
+                None => {
+                    by_name.insert((scope, label.name.as_str()), label.node);
+                }
+            }
+        }
+
+        for label in self.labels.iter() {
+            if label.kind != LabelKind::Hierarchical {
+                continue;
+            }
+            let Some((parent, pins)) = self.instances[label.instance].parent.as_ref() else {
+                continue;
+            };
+            for (name, point) in pins.iter() {
+                if *name == label.name {
+                    let node = self.connectivity.node(*parent, *point);
+                    self.connectivity.union(node, label.node);
+                }
+            }
+        }
+    }
+
+    /// Choose a name for a net, following KiCad's conventions: power and
+    /// global labels are used as is, local and hierarchical labels are
+    /// prefixed with the path of their sheet (preferring the shallowest
+    /// sheet), and unlabelled nets are named after one of their pins.
+    fn net_name(&self, labels: &[&Label], pins: &[&Pin]) -> String {
+        let global = labels
+            .iter()
+            .filter(|l| matches!(l.kind, LabelKind::Power | LabelKind::Global))
+            .min_by(|a, b| a.kind.cmp(&b.kind).then_with(|| compare(&a.name, &b.name)));
+        if let Some(label) = global {
+            return label.name.clone();
+        }
+
+        let local = labels.iter().min_by(|a, b| {
+            let depth = |l: &Label| self.instances[l.instance].uuid_path.len();
+            depth(a)
+                .cmp(&depth(b))
+                .then_with(|| a.kind.cmp(&b.kind))
+                .then_with(|| compare(&a.name, &b.name))
+        });
+        if let Some(label) = local {
+            return format!("{}{}", self.instances[label.instance].name_path, label.name);
+        }
+
+        pin_net_name(pins)
+    }
+
+    fn build(mut self) -> Result<Schematic, SchematicImportError> {
+        let mut schematic = Schematic::new();
+        schematic.register_standard_library()?;
+
+        // Register a Part for each library symbol used by a component, except
+        // for generic parts which use the standard library instead.
+        let mut parts: HashMap<String, SymbolPart> = HashMap::new();
+        for component in self.components.values() {
+            if parts.contains_key(&component.lib_key) {
+                continue;
+            }
+
+            let lib_symbol = component
+                .document
+                .child("lib_symbols")
+                .into_iter()
+                .flat_map(|l| l.children("symbol"))
+                .find(|s| s.atom(1) == Some(component.lib_key.as_str()))
+                .expect("library symbol not found");
+
+            let name = component
+                .lib_key
+                .split_once(':')
+                .map(|(_, name)| name)
+                .unwrap_or(&component.lib_key)
+                .to_string();
+
+            let pins = unit_pins(lib_symbol, 0);
+            let pin_numbers: Vec<&str> = pins.iter().map(|p| p.number.as_str()).collect();
+            if let Some(generic) = generic_part(&mut schematic, &component.lib_key, &pin_numbers) {
+                parts.insert(component.lib_key.clone(), generic);
+                continue;
+            }
+
+            let mut pb = PartBuilder::default();
+            pb.name(name.clone());
+            for pin in pins {
+                pb.port_with_type(&pin.number, &pin.name, pin.pin_type);
+            }
+            for p in lib_symbol.children("property") {
+                if let (Some(key), Some(value)) = (p.atom(1), p.atom(2)) {
+                    if !key.starts_with("ki_") {
+                        pb.metadata(key, value);
+                    }
+                }
+            }
+
+            schematic.unregister_standard_part(&name);
+            let part = schematic.add_part(pb.build()?)?;
+            parts.insert(
+                component.lib_key.clone(),
+                SymbolPart {
+                    part,
+                    value_key: None,
+                    metadata: vec![],
+                },
+            );
+        }
+
+        // Register a Component for each reference.
+        for (reference, component) in self.components.iter() {
+            let part = &parts[&component.lib_key];
+
+            let mut cb = ComponentBuilder::default();
+            cb.name(reference.clone());
+            cb.part(part.part.clone());
+            for (key, value) in component.metadata.iter() {
+                cb.metadata(key, value);
+            }
+            for (key, value) in part.metadata.iter() {
+                cb.metadata(key, value);
+            }
+            if let (Some(key), Some(value)) = (part.value_key, component.metadata.get("Value")) {
+                cb.metadata(key, &normalize(key, value));
+            }
+            schematic.add_component(cb.build()?)?;
+        }
+
+        // Group the pins by the net they are connected to.
+        let mut pins_by_net: HashMap<usize, Vec<&Pin>> = HashMap::new();
+        for pin in self.pins.iter() {
+            let root = self.connectivity.find(pin.node);
+            pins_by_net.entry(root).or_default().push(pin);
+        }
+        let mut labels_by_net: HashMap<usize, Vec<&Label>> = HashMap::new();
+        for label in self.labels.iter() {
+            let root = self.connectivity.find(label.node);
+            labels_by_net.entry(root).or_default().push(label);
+        }
+
+        // Nets are named in order of their pin-derived names, so a name
+        // claimed by two nets goes to the same one each time.
+        let mut nets: Vec<(&usize, &Vec<&Pin>)> = pins_by_net.iter().collect();
+        nets.sort_by_cached_key(|(_, pins)| pin_net_name(pins));
+
+        let mut names: HashSet<String> = HashSet::new();
+        for (root, pins) in nets {
+            let labels = labels_by_net.get(root).map(|l| l.as_slice()).unwrap_or(&[]);
+            // Labels on unconnected nets can end up with the same name, e.g. a
+            // global label named like a sheet's local one, so fall back to
+            // the name of a pin.
+            let mut name = self.net_name(labels, pins);
+            if names.contains(&name) {
+                name = pin_net_name(pins);
+            }
+            names.insert(name.clone());
+            let power_symbol = labels
+                .iter()
+                .filter_map(|l| l.power_symbol.clone())
+                .min_by(|a, b| compare(a, b));
+
+            let net = schematic.add_net(
+                NetBuilder::default()
+                    .name(name.clone())
+                    .power_symbol(power_symbol)
+                    .build()?,
+            )?;
+            for pin in pins.iter() {
+                schematic.connect(&net.name(), &pin.reference, &pin.number)?;
+            }
+        }
+
+        schematic.infer_net_types(&NetTypeOverrides::default())?;
+
+        Ok(schematic)
+    }
+}
+
+/// Name a net after one of its pins, as KiCad does for unlabelled nets, e.g.
+/// `Net-(R1-Pad2)`.
+fn pin_net_name(pins: &[&Pin]) -> String {
+    let pin = pins
+        .iter()
+        .min_by(|a, b| {
+            compare(&a.reference, &b.reference).then_with(|| compare(&a.number, &b.number))
+        })
+        .expect("net has no pins");
+    let pin_name = match pin.name.as_str() {
+        "" | "~" => format!("Pad{}", pin.number),
+        name => name.to_string(),
+    };
+    if pins.len() == 1 {
+        format!("unconnected-({}-{})", pin.reference, pin_name)
+    } else {
+        format!("Net-({}-{})", pin.reference, pin_name)
+    }
+}
+
+/// A pin of a library symbol.
+struct LibPin {
+    number: String,
+    name: String,
+    position: (f64, f64),
+    pin_type: PinType,
+
+    /// Hidden power input pins are implicitly connected to the global net
+    /// named after the pin.
+    implicit_power: bool,
+}
+
+/// Returns the pins of a library symbol that belong to the given unit. Unit 0
+/// returns the pins of every unit.
+fn unit_pins(lib_symbol: &SExpr, unit: i64) -> Vec<LibPin> {
+    let mut seen = HashSet::new();
+    let mut pins = vec![];
+
+    for sub_symbol in lib_symbol.children("symbol") {
+        // Sub-symbols are named `<name>_<unit>_<body style>`.
+        let mut suffix = sub_symbol.atom(1).unwrap_or("").rsplitn(3, '_');
+        let style: i64 = suffix.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+        let sub_unit: i64 = suffix.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        // Alternate (De Morgan) body styles duplicate the pins of the first.
+        if style > 1 || (unit != 0 && sub_unit != 0 && sub_unit != unit) {
+            continue;
+        }
+
+        for pin in sub_symbol.children("pin") {
+            let number = pin.child("number").and_then(|n| n.atom(1)).unwrap_or("");
+            let name = pin.child("name").and_then(|n| n.atom(1)).unwrap_or("");
+            let Some(at) = pin.child("at") else {
+                continue;
+            };
+            let (Some(x), Some(y)) = (at.number(1), at.number(2)) else {
+                continue;
+            };
+            let hidden =
+                pin.has_atom("hide") || pin.child("hide").and_then(|h| h.atom(1)) == Some("yes");
+            // Pins are written as `(pin <electrical type> <graphic style> ...)`.
+            let pin_type = pin.atom(1).and_then(|t| t.parse().ok()).unwrap_or_default();
+
+            if seen.insert(number.to_string()) {
+                pins.push(LibPin {
+                    number: number.to_string(),
+                    name: name.to_string(),
+                    position: (x, y),
+                    pin_type,
+                    implicit_power: hidden && pin_type == PinType::PowerIn,
+                });
+            }
+        }
+    }
+
+    pins
+}
+
+/// Convert a pin position in library coordinates (Y up) to a point on the
+/// sheet (Y down), given the placement of its symbol.
+fn transform(origin: Point, angle: f64, mirror: Option<&str>, pin: (f64, f64)) -> Point {
+    let (mut x, mut y) = (to_units(pin.0), -to_units(pin.1));
+
+    // Each quarter turn rotates the symbol counter-clockwise on the sheet.
+    let turns = ((angle / 90.0).round() as i64).rem_euclid(4);
+    for _ in 0..turns {
+        (x, y) = (y, -x);
+    }
+
+    match mirror {
+        Some("x") => y = -y,
+        Some("y") => x = -x,
+        _ => {}
+    }
+
+    (origin.0 + x, origin.1 + y)
+}
+
+/// Import a KiCad schematic (`.kicad_sch`) into a Schematic. `path` should be
+/// the root sheet of the project; hierarchical sheets are loaded relative to
+/// it. Nets are resolved from wires, labels, power symbols and hierarchical
+/// pins. Buses are not supported.
+pub fn schematics_from_kicad_schematic(path: &Path) -> Result<Schematic, SchematicImportError> {
+    let mut reader = SchematicReader {
+        documents: HashMap::new(),
+        instances: vec![],
+        root_uuid: String::new(),
+        legacy_references: HashMap::new(),
+        connectivity: Connectivity::default(),
+        labels: vec![],
+        pins: vec![],
+        components: BTreeMap::new(),
+    };
+
+    let root = reader.load(path)?;
+    reader.root_uuid = root
+        .child("uuid")
+        .and_then(|u| u.atom(1))
+        .unwrap_or("")
+        .to_string();
+
+    for instance in root
+        .child("symbol_instances")
+        .into_iter()
+        .flat_map(|s| s.children("path"))
+    {
+        if let (Some(path), Some(reference)) = (
+            instance.atom(1),
+            instance.child("reference").and_then(|r| r.atom(1)),
+        ) {
+            let unit = instance.child("unit").and_then(|u| u.number(1));
+            reader.legacy_references.insert(
+                path.to_string(),
+                (reference.to_string(), unit.map(|u| u as i64)),
+            );
+        }
+    }
+
+    reader.add_instances(
+        path,
+        SheetInstance {
+            document: root,
+            uuid_path: vec![],
+            name_path: "/".to_string(),
+            sheet_name: None,
+            sheet_file: None,
+            parent: None,
+        },
+    )?;
+
+    for index in 0..reader.instances.len() {
+        reader.add_connectivity(index);
+    }
+    reader.connect_labels();
+
+    reader.build()
+}
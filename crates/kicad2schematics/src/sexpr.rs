@@ -0,0 +1,144 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::SchematicImportError;
+
+/// A node in a KiCad s-expression file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    /// Returns the first atom of a list, e.g. `symbol` for `(symbol ...)`.
+    pub fn head(&self) -> Option<&str> {
+        match self {
+            SExpr::List(items) => items.first().and_then(|i| i.as_atom()),
+            SExpr::Atom(_) => None,
+        }
+    }
+
+    pub fn as_atom(&self) -> Option<&str> {
+        match self {
+            SExpr::Atom(atom) => Some(atom.as_str()),
+            SExpr::List(_) => None,
+        }
+    }
+
+    pub fn items(&self) -> &[SExpr] {
+        match self {
+            SExpr::List(items) => items.as_slice(),
+            SExpr::Atom(_) => &[],
+        }
+    }
+
+    /// Returns the atom at `index` within a list, where index 0 is the head.
+    pub fn atom(&self, index: usize) -> Option<&str> {
+        self.items().get(index).and_then(|i| i.as_atom())
+    }
+
+    pub fn number(&self, index: usize) -> Option<f64> {
+        self.atom(index).and_then(|a| a.parse().ok())
+    }
+
+    /// Returns every child list with the given head.
+    pub fn children<'a>(&'a self, head: &'a str) -> impl Iterator<Item = &'a SExpr> + 'a {
+        self.items()
+            .iter()
+            .filter(move |item| item.head() == Some(head))
+    }
+
+    /// Returns the first child list with the given head.
+    pub fn child(&self, head: &str) -> Option<&SExpr> {
+        self.items().iter().find(|item| item.head() == Some(head))
+    }
+
+    /// Returns true if the list contains the given bare atom, e.g. `hide`.
+    pub fn has_atom(&self, atom: &str) -> bool {
+        self.items()
+            .iter()
+            .skip(1)
+            .any(|i| i.as_atom() == Some(atom))
+    }
+}
+
+/// Parse a single s-expression, such as the contents of a `.kicad_sch` file.
+pub fn parse(input: &str) -> Result<SExpr, SchematicImportError> {
+    let mut chars = input.chars().peekable();
+    skip_whitespace(&mut chars);
+    let expr = parse_expr(&mut chars)?;
+    skip_whitespace(&mut chars);
+    if chars.peek().is_some() {
+        return Err(SchematicImportError::InterpretationError(
+            "Unexpected trailing content after s-expression".to_string(),
+        ));
+    }
+    Ok(expr)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn parse_expr(chars: &mut Peekable<Chars>) -> Result<SExpr, SchematicImportError> {
+    match chars.peek() {
+        Some('(') => {
+            chars.next();
+            let mut items = vec![];
+            loop {
+                skip_whitespace(chars);
+                match chars.peek() {
+                    Some(')') => {
+                        chars.next();
+                        return Ok(SExpr::List(items));
+                    }
+                    Some(_) => items.push(parse_expr(chars)?),
+                    None => {
+                        return Err(SchematicImportError::InterpretationError(
+                            "Unexpected end of file: unbalanced parentheses".to_string(),
+                        ))
+                    }
+                }
+            }
+        }
+        Some('"') => {
+            chars.next();
+            let mut atom = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => return Ok(SExpr::Atom(atom)),
+                    Some('\\') => match chars.next() {
+                        Some('n') => atom.push('\n'),
+                        Some('t') => atom.push('\t'),
+                        Some(c) => atom.push(c),
+                        None => break,
+                    },
+                    Some(c) => atom.push(c),
+                    None => break,
+                }
+            }
+            Err(SchematicImportError::InterpretationError(
+                "Unexpected end of file: unterminated string".to_string(),
+            ))
+        }
+        Some(')') => Err(SchematicImportError::InterpretationError(
+            "Unexpected closing parenthesis".to_string(),
+        )),
+        Some(_) => {
+            let mut atom = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' {
+                    break;
+                }
+                atom.push(c);
+                chars.next();
+            }
+            Ok(SExpr::Atom(atom))
+        }
+        None => Err(SchematicImportError::InterpretationError(
+            "Unexpected end of file".to_string(),
+        )),
+    }
+}
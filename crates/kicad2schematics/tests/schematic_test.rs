@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use kicad2schematics::schematics_from_kicad_schematic;
+use schematics::{diff::Connection, net::NetType, part::PinType, Schematic};
+
+fn net_connections(schematic: &Schematic, net: &str) -> Vec<String> {
+    let net = schematic.get_net(net).expect("net not found");
+    let mut connections: Vec<String> = net
+        .as_deref()
+        .connections
+        .iter()
+        .map(|(c, p)| Connection::new(c, p).to_string())
+        .collect();
+    connections.sort();
+    connections
+}
+
+#[test]
+fn test_hierarchical_schematic() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy/hierarchy.kicad_sch");
+    let schematic = schematics_from_kicad_schematic(&path).unwrap();
+
+    let mut components: Vec<String> = schematic
+        .components_iter()
+        .map(|c| c.as_deref().name.clone())
+        .collect();
+    components.sort();
+    assert_eq!(components, vec!["D1", "R1"]);
+
+    let d1 = schematic.get_component("D1").unwrap();
+    assert_eq!(d1.as_deref().sheet_name(), Some("Led"));
+    assert_eq!(d1.as_deref().sheet_path(), vec!["Led"]);
+    assert_eq!(
+        d1.as_deref().sheet_tstamps(),
+        vec!["00000000-0000-0000-0000-000000000040"]
+    );
+    assert_eq!(d1.as_deref().part.as_deref().name, "LED");
+    let anode = d1.as_deref().get_port("2").unwrap();
+    assert_eq!(anode.as_deref().pin_type, PinType::Passive);
+    let r1 = schematic.get_component("R1").unwrap();
+    assert_eq!(r1.as_deref().sheet_name(), None);
+    assert!(r1.as_deref().sheet_path().is_empty());
+    // Generic symbols use the standard library parts.
+    assert_eq!(r1.as_deref().part.as_deref().name, "Resistor");
+    assert_eq!(r1.as_deref().metadata["resistance"], "1kohm");
+
+    let mut nets: Vec<String> = schematic.nets_iter().map(|n| n.name()).collect();
+    nets.sort();
+    assert_eq!(nets, vec!["/MID", "GND", "VCC"]);
+
+    assert_eq!(net_connections(&schematic, "VCC"), vec!["R1.1"]);
+    assert_eq!(net_connections(&schematic, "/MID"), vec!["D1.2", "R1.2"]);
+    assert_eq!(net_connections(&schematic, "GND"), vec!["D1.1"]);
+
+    let gnd = schematic.get_net("GND").unwrap();
+    assert_eq!(gnd.as_deref().net_type, NetType::Ground);
+    assert_eq!(gnd.as_deref().power_symbol.as_deref(), Some("power:GND"));
+}
+
+#[test]
+fn test_repeated_sheet() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy/repeated.kicad_sch");
+    let schematic = schematics_from_kicad_schematic(&path).unwrap();
+
+    let mut components: Vec<String> = schematic
+        .components_iter()
+        .map(|c| c.as_deref().name.clone())
+        .collect();
+    components.sort();
+    assert_eq!(components, vec!["D1", "D2", "R1", "R2"]);
+
+    // Each use of the sheet has its own path and references.
+    let d2 = schematic.get_component("D2").unwrap();
+    assert_eq!(d2.as_deref().sheet_path(), vec!["Led2"]);
+    assert_eq!(
+        d2.as_deref().sheet_tstamps(),
+        vec!["00000000-0000-0000-0000-000000000090"]
+    );
+
+    assert_eq!(net_connections(&schematic, "/MID1"), vec!["D1.2", "R1.2"]);
+    assert_eq!(net_connections(&schematic, "/MID2"), vec!["D2.2", "R2.2"]);
+    assert_eq!(net_connections(&schematic, "GND"), vec!["D1.1", "D2.1"]);
+}
+
+#[test]
+fn test_labels_in_sheet() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy/labels.kicad_sch");
+    let schematic = schematics_from_kicad_schematic(&path).unwrap();
+
+    let mut nets: Vec<String> = schematic.nets_iter().map(|n| n.name()).collect();
+    nets.sort();
+    assert_eq!(
+        nets,
+        vec![
+            "/Child/OUT",
+            "/Child/SIG",
+            "unconnected-(R3-Pad1)",
+            "unconnected-(R3-Pad2)"
+        ]
+    );
+
+    // A local and a hierarchical label with the same name are connected.
+    assert_eq!(
+        net_connections(&schematic, "/Child/SIG"),
+        vec!["R1.2", "R2.2"]
+    );
+    // So are hierarchical labels without a sheet pin in the parent.
+    assert_eq!(
+        net_connections(&schematic, "/Child/OUT"),
+        vec!["R1.1", "R2.1"]
+    );
+    // A global label taking the name of the sheet's net falls back to the
+    // name of its pin.
+    assert_eq!(
+        net_connections(&schematic, "unconnected-(R3-Pad2)"),
+        vec!["R3.2"]
+    );
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use natord::compare;
+use serde::Serialize;
+
+use crate::{
+    component::Component,
+    part::{FOOTPRINT_KEY, MPN_KEY},
+    standard_library, Schematic,
+};
+
+/// The metadata keys each field of a BOM line is read from, in order of
+/// preference. The capitalized ones are KiCad's fields.
+const VALUE_KEYS: &[&str] = &["Value"];
+const MPN_KEYS: &[&str] = &["MPN", MPN_KEY, "Manufacturer_Part_Number"];
+const MANUFACTURER_KEYS: &[&str] = &["Manufacturer", "manufacturer"];
+const FOOTPRINT_KEYS: &[&str] = &["Footprint", FOOTPRINT_KEY];
+const LCSC_KEYS: &[&str] = &["LCSC", "lcsc", "LCSC Part"];
+
+/// The components that share a part and the fields they're ordered by, which
+/// are bought together.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BomLine {
+    /// The names of the components, in natural order.
+    pub designators: Vec<String>,
+    pub quantity: usize,
+    pub part: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mpn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<String>,
+    /// The LCSC part number, which JLCPCB assembles from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lcsc: Option<String>,
+}
+
+impl BomLine {
+    /// The designators with runs of consecutive numbers collapsed into
+    /// ranges, e.g. `R1-R4, R7`.
+    pub fn designator_ranges(&self) -> String {
+        collapse_designators(&self.designators)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Bom {
+    /// The lines, in natural order of their first designator.
+    pub lines: Vec<BomLine>,
+}
+
+/// Split a designator into its prefix and number, e.g. `R12` into `R` and
+/// `12`.
+fn split_designator(designator: &str) -> Option<(&str, u64)> {
+    let digits = designator.len()
+        - designator
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+    let (prefix, number) = designator.split_at(digits);
+    // Leading zeros would be lost in a range, e.g. in `R01`.
+    if number.len() > 1 && number.starts_with('0') {
+        return None;
+    }
+    number.parse().ok().map(|number| (prefix, number))
+}
+
+/// Consecutive designators with the same prefix, e.g. `R1` to `R4`.
+struct Run<'a> {
+    first: &'a str,
+    last: &'a str,
+    count: usize,
+}
+
+/// Join designators with `, `, collapsing runs of three or more consecutive
+/// numbers with the same prefix into a range, e.g. `R1-R4, R7`. The
+/// designators are expected in natural order.
+pub fn collapse_designators(designators: &[String]) -> String {
+    let mut runs: Vec<Run> = vec![];
+    for designator in designators.iter() {
+        if let Some(run) = runs.last_mut() {
+            let continues = match (split_designator(run.last), split_designator(designator)) {
+                (Some((last_prefix, last)), Some((prefix, number))) => {
+                    last_prefix == prefix && last + 1 == number
+                }
+                _ => false,
+            };
+            if continues {
+                run.last = designator;
+                run.count += 1;
+                continue;
+            }
+        }
+        runs.push(Run {
+            first: designator,
+            last: designator,
+            count: 1,
+        });
+    }
+
+    let mut parts = vec![];
+    for run in runs {
+        match run.count {
+            1 => parts.push(run.first.to_string()),
+            2 => parts.extend([run.first.to_string(), run.last.to_string()]),
+            _ => parts.push(format!("{}-{}", run.first, run.last)),
+        }
+    }
+    parts.join(", ")
+}
+
+/// The first non-empty value of `keys` in the component's metadata, falling
+/// back to its part's.
+fn metadata(component: &Component, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| component.get_metadata(key))
+        .find(|value| !value.trim().is_empty())
+}
+
+fn bom_line(component: &Component) -> BomLine {
+    let part = component.part.as_deref().name.clone();
+    // Standard library parts keep their value under their own key when
+    // they're not imported from KiCad, e.g. `resistance`.
+    let value_key = standard_library::value_key(&part);
+    let value_keys: Vec<&str> = VALUE_KEYS.iter().copied().chain(value_key).collect();
+
+    BomLine {
+        designators: vec![component.name.clone()],
+        quantity: 1,
+        value: metadata(component, &value_keys),
+        mpn: metadata(component, MPN_KEYS),
+        manufacturer: metadata(component, MANUFACTURER_KEYS),
+        footprint: metadata(component, FOOTPRINT_KEYS),
+        lcsc: metadata(component, LCSC_KEYS),
+        part,
+    }
+}
+
+fn write_csv(header: &[&str], rows: Vec<Vec<String>>) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    let contents = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8(contents).expect("CSV is written from strings"))
+}
+
+impl Bom {
+    /// A CSV with a line per group of components and a column per field.
+    pub fn to_csv(&self) -> Result<String, csv::Error> {
+        write_csv(
+            &[
+                "Designators",
+                "Quantity",
+                "Part",
+                "Value",
+                "MPN",
+                "Manufacturer",
+                "Footprint",
+                "LCSC",
+            ],
+            self.lines
+                .iter()
+                .map(|line| {
+                    vec![
+                        line.designator_ranges(),
+                        line.quantity.to_string(),
+                        line.part.clone(),
+                        line.value.clone().unwrap_or_default(),
+                        line.mpn.clone().unwrap_or_default(),
+                        line.manufacturer.clone().unwrap_or_default(),
+                        line.footprint.clone().unwrap_or_default(),
+                        line.lcsc.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        )
+    }
+
+    /// A CSV in the layout of JLCPCB's assembly BOM. The designators are
+    /// listed one by one rather than as ranges, since JLCPCB matches them
+    /// against the placement file.
+    pub fn to_jlcpcb_csv(&self) -> Result<String, csv::Error> {
+        write_csv(
+            &["Comment", "Designator", "Footprint", "JLCPCB Part #"],
+            self.lines
+                .iter()
+                .map(|line| {
+                    vec![
+                        line.value.clone().unwrap_or(line.part.clone()),
+                        line.designators.join(","),
+                        line.footprint.clone().unwrap_or_default(),
+                        line.lcsc.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        )
+    }
+
+    /// A CSV in the layout Mouser's BOM tool imports, skipping the lines
+    /// without an MPN, which can't be ordered.
+    pub fn to_mouser_csv(&self) -> Result<String, csv::Error> {
+        write_csv(
+            &[
+                "Mfr Part Number",
+                "Manufacturer",
+                "Quantity",
+                "Customer Part Number",
+                "Description",
+            ],
+            self.lines
+                .iter()
+                .filter(|line| line.mpn.is_some())
+                .map(|line| {
+                    let description = match line.value.as_ref() {
+                        Some(value) => format!("{} {}", line.part, value),
+                        None => line.part.clone(),
+                    };
+                    vec![
+                        line.mpn.clone().unwrap_or_default(),
+                        line.manufacturer.clone().unwrap_or_default(),
+                        line.quantity.to_string(),
+                        line.designator_ranges(),
+                        description,
+                    ]
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Schematic {
+    /// The bill of materials, grouping the components that share a part,
+    /// value, MPN, manufacturer, footprint and LCSC part number.
+    pub fn bom(&self) -> Bom {
+        let mut components: Vec<_> = self.components_iter().collect();
+        components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+        let mut lines: Vec<BomLine> = vec![];
+        let mut index: BTreeMap<BomKey, usize> = BTreeMap::new();
+        for component in components {
+            let line = bom_line(&component.as_deref());
+            let key = BomKey::of(&line);
+            match index.get(&key) {
+                Some(i) => {
+                    lines[*i].designators.extend(line.designators);
+                    lines[*i].quantity += 1;
+                }
+                None => {
+                    index.insert(key, lines.len());
+                    lines.push(line);
+                }
+            }
+        }
+
+        Bom { lines }
+    }
+}
+
+/// Everything but the designators and quantity of a line.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct BomKey([Option<String>; 6]);
+
+impl BomKey {
+    fn of(line: &BomLine) -> Self {
+        BomKey([
+            Some(line.part.clone()),
+            line.value.clone(),
+            line.mpn.clone(),
+            line.manufacturer.clone(),
+            line.footprint.clone(),
+            line.lcsc.clone(),
+        ])
+    }
+}
@@ -11,6 +11,32 @@ use crate::{
     SchematicError,
 };
 
+/// The property KiCad uses to record the sheet a component was placed on.
+pub const KICAD_SHEET_NAME_KEY: &str = "Sheetname";
+
+/// The property KiCad uses to record the file of the sheet a component was
+/// placed on.
+pub const KICAD_SHEET_FILE_KEY: &str = "Sheetfile";
+
+/// The names of the sheets leading to the one a component was placed on,
+/// e.g. `/Power/LDO/`, as in the `sheetpath` of a KiCad netlist.
+pub const KICAD_SHEET_PATH_KEY: &str = "Sheetpath";
+
+/// The UUIDs of the sheet symbols leading to the sheet a component was placed
+/// on. Unlike the names, these tell apart sheets with the same name.
+pub const KICAD_SHEET_TSTAMPS_KEY: &str = "Sheettstamps";
+
+/// The UUID of a component's symbol, which is shared by every instance of a
+/// sheet that is used more than once.
+pub const KICAD_TSTAMP_KEY: &str = "Tstamp";
+
+fn split_sheet_path(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct ComponentRef(pub Rc<RefCell<Component>>);
 
@@ -55,6 +81,44 @@ impl Component {
     pub fn get_port(&self, name: &str) -> Option<PortRef> {
         self.part.as_deref().get_port(name)
     }
+
+    /// Returns the value of a metadata key, falling back to the metadata of
+    /// the part if the component doesn't override it.
+    pub fn get_metadata(&self, key: &str) -> Option<String> {
+        self.metadata
+            .get(key)
+            .or(self.part.as_deref().metadata.get(key))
+            .cloned()
+    }
+
+    /// Returns the name of the schematic sheet the component was placed on, if
+    /// the importer recorded one.
+    pub fn sheet_name(&self) -> Option<&str> {
+        self.metadata.get(KICAD_SHEET_NAME_KEY).map(|s| s.as_str())
+    }
+
+    /// Returns the names of the sheets leading from the root sheet to the
+    /// one the component was placed on, e.g. `["Power", "LDO"]`, or an empty
+    /// list for the root sheet. Falls back to the sheet name when that's all
+    /// the importer recorded.
+    pub fn sheet_path(&self) -> Vec<String> {
+        match self.metadata.get(KICAD_SHEET_PATH_KEY) {
+            Some(path) => split_sheet_path(path),
+            None => self
+                .sheet_name()
+                .map(|s| vec![s.to_string()])
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the UUIDs of the sheet symbols leading to the sheet the
+    /// component was placed on, if the importer recorded them.
+    pub fn sheet_tstamps(&self) -> Vec<String> {
+        self.metadata
+            .get(KICAD_SHEET_TSTAMPS_KEY)
+            .map(|path| split_sheet_path(path))
+            .unwrap_or_default()
+    }
 }
 
 impl ComponentBuilder {
@@ -0,0 +1,601 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use natord::compare;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{ComponentRef, KICAD_SHEET_TSTAMPS_KEY, KICAD_TSTAMP_KEY},
+    net::{NetRef, NetType},
+    part::{PinType, PortRef},
+    Schematic, SchematicError,
+};
+
+/// A pin on a specific component, identified by name rather than by handle so
+/// that it can be compared across schematics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Connection {
+    pub component: String,
+    pub terminal_identifier: String,
+}
+
+impl Connection {
+    pub fn new(component: &ComponentRef, port: &PortRef) -> Self {
+        Self {
+            component: component.as_deref().name.clone(),
+            terminal_identifier: port.as_deref().terminal_identifier.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.component, self.terminal_identifier)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum PartChange {
+    Added {
+        part: String,
+    },
+    Removed {
+        part: String,
+    },
+    PortAdded {
+        part: String,
+        terminal_identifier: String,
+        signal: String,
+    },
+    PortRemoved {
+        part: String,
+        terminal_identifier: String,
+        signal: String,
+    },
+    /// The pin still exists, but is now bound to a different signal.
+    PinRemapped {
+        part: String,
+        terminal_identifier: String,
+        old_signal: String,
+        new_signal: String,
+    },
+    /// The electrical type of a pin changed.
+    PinTypeChanged {
+        part: String,
+        terminal_identifier: String,
+        old: PinType,
+        new: PinType,
+    },
+    DatasheetChanged {
+        part: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    MetadataChanged {
+        part: String,
+        key: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ComponentChange {
+    Added {
+        component: String,
+        part: String,
+    },
+    Removed {
+        component: String,
+        part: String,
+    },
+    /// The component now instantiates a different part.
+    PartSwapped {
+        component: String,
+        old_part: String,
+        new_part: String,
+    },
+    MetadataChanged {
+        component: String,
+        key: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A pin of the component is connected to a different net. `None` means
+    /// the pin is unconnected on that side.
+    MovedNet {
+        component: String,
+        terminal_identifier: String,
+        old_net: Option<String>,
+        new_net: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum NetChange {
+    Added {
+        net: String,
+        connections: Vec<Connection>,
+    },
+    Removed {
+        net: String,
+        connections: Vec<Connection>,
+    },
+    Connected {
+        net: String,
+        connection: Connection,
+    },
+    Disconnected {
+        net: String,
+        connection: Connection,
+    },
+    /// A net that only exists on one side was paired with a net that only
+    /// exists on the other, because they connect mostly the same pins. Any
+    /// further changes to the net are reported under the new name.
+    Renamed {
+        old_net: String,
+        new_net: String,
+        similarity: f64,
+    },
+    NetTypeChanged {
+        net: String,
+        old: NetType,
+        new: NetType,
+    },
+}
+
+/// `SchematicDiff` describes how to get from one schematic to another in
+/// terms of the schematic model: parts, components and nets. Changes are
+/// sorted by the (natural) order of the name of the item they apply to.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SchematicDiff {
+    pub parts: Vec<PartChange>,
+    pub components: Vec<ComponentChange>,
+    pub nets: Vec<NetChange>,
+}
+
+impl SchematicDiff {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty() && self.components.is_empty() && self.nets.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts.len() + self.components.len() + self.nets.len()
+    }
+}
+
+/// Options controlling how two schematics are compared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    /// Nets that only exist on one side are reported as renamed if the
+    /// Jaccard similarity of their connections (shared pins over all pins) is
+    /// at least this value. `None` disables rename detection, so nets are only
+    /// matched by name. Must be from 0 to 1.
+    pub net_rename_threshold: Option<f64>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            net_rename_threshold: Some(0.5),
+        }
+    }
+}
+
+/// Returns the names present in either map, in natural order.
+fn sorted_union<'a, T>(
+    left: &'a HashMap<String, T>,
+    right: &'a HashMap<String, T>,
+) -> Vec<&'a String> {
+    let mut names: Vec<&String> = left
+        .keys()
+        .chain(right.keys().filter(|name| !left.contains_key(*name)))
+        .collect();
+    names.sort_by(|a, b| compare(a, b));
+    names
+}
+
+/// Metadata that identifies where a component was placed in KiCad, rather
+/// than describing the design, so isn't compared.
+const IGNORED_METADATA_KEYS: &[&str] = &[KICAD_SHEET_TSTAMPS_KEY, KICAD_TSTAMP_KEY];
+
+/// Returns every key whose value differs between the two maps, in order,
+/// along with the old and new values.
+fn diff_metadata(
+    left: &HashMap<String, String>,
+    right: &HashMap<String, String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !IGNORED_METADATA_KEYS.contains(&key.as_str()))
+        .filter(|key| left.get(*key) != right.get(*key))
+        .map(|key| (key.clone(), left.get(key).cloned(), right.get(key).cloned()))
+        .collect()
+}
+
+fn net_connections(net: &NetRef) -> BTreeSet<Connection> {
+    net.as_deref()
+        .connections
+        .iter()
+        .map(|(c, p)| Connection::new(c, p))
+        .collect()
+}
+
+/// A pairing of a net that was removed with a net that was added.
+struct NetRename {
+    old_net: String,
+    new_net: String,
+    similarity: f64,
+}
+
+fn sorted_connections(connections: impl Iterator<Item = Connection>) -> Vec<Connection> {
+    let mut connections: Vec<Connection> = connections.collect();
+    connections.sort_by(|a, b| {
+        compare(&a.component, &b.component)
+            .then_with(|| compare(&a.terminal_identifier, &b.terminal_identifier))
+    });
+    connections
+}
+
+impl Schematic {
+    /// Compare this schematic against `other`, returning the changes needed to
+    /// turn `self` into `other`.
+    pub fn diff(&self, other: &Schematic) -> SchematicDiff {
+        self.diff_with_options(other, &DiffOptions::default())
+            .expect("the default options are valid")
+    }
+
+    /// Like [`Schematic::diff`], with options. Fails if the rename threshold
+    /// isn't from 0 to 1.
+    pub fn diff_with_options(
+        &self,
+        other: &Schematic,
+        options: &DiffOptions,
+    ) -> Result<SchematicDiff, SchematicError> {
+        let renames = match options.net_rename_threshold {
+            Some(threshold) if !(0.0..=1.0).contains(&threshold) => {
+                return Err(SchematicError::InvalidRenameThreshold(threshold))
+            }
+            Some(threshold) => self.match_renamed_nets(other, threshold),
+            None => vec![],
+        };
+
+        Ok(SchematicDiff {
+            parts: self.diff_parts(other),
+            components: self.diff_components(other, &renames),
+            nets: self.diff_nets(other, &renames),
+        })
+    }
+
+    fn diff_parts(&self, other: &Schematic) -> Vec<PartChange> {
+        let mut changes = vec![];
+
+        for name in sorted_union(&self.parts_by_name, &other.parts_by_name) {
+            let (left, right) = match (self.parts_by_name.get(name), other.parts_by_name.get(name))
+            {
+                (Some(left), Some(right)) => (left.as_deref(), right.as_deref()),
+                (Some(_), None) => {
+                    changes.push(PartChange::Removed { part: name.clone() });
+                    continue;
+                }
+                (None, Some(_)) => {
+                    changes.push(PartChange::Added { part: name.clone() });
+                    continue;
+                }
+                (None, None) => unreachable!(),
+            };
+
+            let terminals = sorted_union(
+                &left.ports_by_terminal_identifier,
+                &right.ports_by_terminal_identifier,
+            );
+            for terminal in terminals {
+                let left_signal = left.get_port(terminal).map(|p| p.as_deref().signal.clone());
+                let right_signal = right
+                    .get_port(terminal)
+                    .map(|p| p.as_deref().signal.clone());
+
+                match (left_signal, right_signal) {
+                    (Some(old_signal), Some(new_signal)) if old_signal != new_signal => changes
+                        .push(PartChange::PinRemapped {
+                            part: name.clone(),
+                            terminal_identifier: terminal.clone(),
+                            old_signal,
+                            new_signal,
+                        }),
+                    (Some(signal), None) => changes.push(PartChange::PortRemoved {
+                        part: name.clone(),
+                        terminal_identifier: terminal.clone(),
+                        signal,
+                    }),
+                    (None, Some(signal)) => changes.push(PartChange::PortAdded {
+                        part: name.clone(),
+                        terminal_identifier: terminal.clone(),
+                        signal,
+                    }),
+                    _ => {}
+                }
+
+                if let (Some(old), Some(new)) = (left.get_port(terminal), right.get_port(terminal))
+                {
+                    let (old, new) = (old.as_deref().pin_type, new.as_deref().pin_type);
+                    if old != new {
+                        changes.push(PartChange::PinTypeChanged {
+                            part: name.clone(),
+                            terminal_identifier: terminal.clone(),
+                            old,
+                            new,
+                        });
+                    }
+                }
+            }
+
+            if left.datasheet_url != right.datasheet_url {
+                changes.push(PartChange::DatasheetChanged {
+                    part: name.clone(),
+                    old: left.datasheet_url.clone(),
+                    new: right.datasheet_url.clone(),
+                });
+            }
+
+            for (key, old, new) in diff_metadata(&left.metadata, &right.metadata) {
+                changes.push(PartChange::MetadataChanged {
+                    part: name.clone(),
+                    key,
+                    old,
+                    new,
+                });
+            }
+        }
+
+        changes
+    }
+
+    /// Build a mapping from each connected pin to the name of its net.
+    fn nets_by_connection(&self) -> HashMap<Connection, String> {
+        self.nets_by_name
+            .iter()
+            .flat_map(|(name, net)| {
+                net.as_deref()
+                    .connections
+                    .iter()
+                    .map(|(c, p)| (Connection::new(c, p), name.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn diff_components(&self, other: &Schematic, renames: &[NetRename]) -> Vec<ComponentChange> {
+        let mut changes = vec![];
+        let renamed_to: HashMap<&String, &String> =
+            renames.iter().map(|r| (&r.old_net, &r.new_net)).collect();
+        let left_nets = self.nets_by_connection();
+        let right_nets = other.nets_by_connection();
+
+        for name in sorted_union(&self.components_by_name, &other.components_by_name) {
+            let (left, right) = match (
+                self.components_by_name.get(name),
+                other.components_by_name.get(name),
+            ) {
+                (Some(left), Some(right)) => (left.as_deref(), right.as_deref()),
+                (Some(left), None) => {
+                    changes.push(ComponentChange::Removed {
+                        component: name.clone(),
+                        part: left.as_deref().part.as_deref().name.clone(),
+                    });
+                    continue;
+                }
+                (None, Some(right)) => {
+                    changes.push(ComponentChange::Added {
+                        component: name.clone(),
+                        part: right.as_deref().part.as_deref().name.clone(),
+                    });
+                    continue;
+                }
+                (None, None) => unreachable!(),
+            };
+
+            let old_part = left.part.as_deref().name.clone();
+            let new_part = right.part.as_deref().name.clone();
+            if old_part != new_part {
+                changes.push(ComponentChange::PartSwapped {
+                    component: name.clone(),
+                    old_part,
+                    new_part,
+                });
+            }
+
+            for (key, old, new) in diff_metadata(&left.metadata, &right.metadata) {
+                changes.push(ComponentChange::MetadataChanged {
+                    component: name.clone(),
+                    key,
+                    old,
+                    new,
+                });
+            }
+
+            // Only pins which exist on both sides can move between nets; pins
+            // that were added or removed are covered by the part changes.
+            let left_part = left.part.as_deref();
+            let right_part = right.part.as_deref();
+            let mut terminals: Vec<&String> = left_part
+                .ports_by_terminal_identifier
+                .keys()
+                .filter(|t| right_part.ports_by_terminal_identifier.contains_key(*t))
+                .collect();
+            terminals.sort_by(|a, b| compare(a, b));
+
+            for terminal in terminals {
+                let connection = Connection {
+                    component: name.clone(),
+                    terminal_identifier: terminal.clone(),
+                };
+                let old_net = left_nets.get(&connection).cloned();
+                let new_net = right_nets.get(&connection).cloned();
+
+                // A pin on a renamed net has not moved.
+                let renamed_net = old_net
+                    .as_ref()
+                    .map(|n| renamed_to.get(n).copied().unwrap_or(n));
+                if renamed_net != new_net.as_ref() {
+                    changes.push(ComponentChange::MovedNet {
+                        component: name.clone(),
+                        terminal_identifier: terminal.clone(),
+                        old_net,
+                        new_net,
+                    });
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Pair up nets which only exist on one side by the overlap of their
+    /// connections. Candidate pairs are accepted greedily, most similar first.
+    fn match_renamed_nets(&self, other: &Schematic, threshold: f64) -> Vec<NetRename> {
+        let removed: Vec<(&String, BTreeSet<Connection>)> = self
+            .nets_by_name
+            .iter()
+            .filter(|(name, _)| !other.nets_by_name.contains_key(*name))
+            .map(|(name, net)| (name, net_connections(net)))
+            .collect();
+        let added: Vec<(&String, BTreeSet<Connection>)> = other
+            .nets_by_name
+            .iter()
+            .filter(|(name, _)| !self.nets_by_name.contains_key(*name))
+            .map(|(name, net)| (name, net_connections(net)))
+            .collect();
+
+        // Index the added nets by connection, so that we only compare nets
+        // which share at least one pin.
+        let mut added_by_connection: HashMap<&Connection, Vec<usize>> = HashMap::new();
+        for (index, (_, connections)) in added.iter().enumerate() {
+            for connection in connections.iter() {
+                added_by_connection
+                    .entry(connection)
+                    .or_default()
+                    .push(index);
+            }
+        }
+
+        let mut candidates = vec![];
+        for (removed_index, (_, connections)) in removed.iter().enumerate() {
+            let mut shared: HashMap<usize, usize> = HashMap::new();
+            for connection in connections.iter() {
+                for added_index in added_by_connection.get(connection).into_iter().flatten() {
+                    *shared.entry(*added_index).or_default() += 1;
+                }
+            }
+
+            for (added_index, shared) in shared {
+                let union = connections.len() + added[added_index].1.len() - shared;
+                let similarity = shared as f64 / union as f64;
+                if similarity >= threshold {
+                    candidates.push((similarity, removed_index, added_index));
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then_with(|| compare(removed[a.1].0, removed[b.1].0))
+                .then_with(|| compare(added[a.2].0, added[b.2].0))
+        });
+
+        let mut renames = vec![];
+        let mut used_removed = HashSet::new();
+        let mut used_added = HashSet::new();
+        for (similarity, removed_index, added_index) in candidates {
+            if used_removed.contains(&removed_index) || used_added.contains(&added_index) {
+                continue;
+            }
+            used_removed.insert(removed_index);
+            used_added.insert(added_index);
+            renames.push(NetRename {
+                old_net: removed[removed_index].0.clone(),
+                new_net: added[added_index].0.clone(),
+                similarity,
+            });
+        }
+
+        renames
+    }
+
+    fn diff_nets(&self, other: &Schematic, renames: &[NetRename]) -> Vec<NetChange> {
+        let mut changes = vec![];
+        let renamed_from: HashMap<&String, &NetRename> =
+            renames.iter().map(|r| (&r.new_net, r)).collect();
+        let renamed_to: HashSet<&String> = renames.iter().map(|r| &r.old_net).collect();
+
+        for name in sorted_union(&self.nets_by_name, &other.nets_by_name) {
+            let (left, right) = match (self.nets_by_name.get(name), other.nets_by_name.get(name)) {
+                (Some(left), Some(right)) => (left, right),
+                (Some(_), None) if renamed_to.contains(name) => continue,
+                (Some(left), None) => {
+                    changes.push(NetChange::Removed {
+                        net: name.clone(),
+                        connections: sorted_connections(net_connections(left).into_iter()),
+                    });
+                    continue;
+                }
+                (None, Some(right)) => match renamed_from.get(name) {
+                    Some(rename) => {
+                        changes.push(NetChange::Renamed {
+                            old_net: rename.old_net.clone(),
+                            new_net: rename.new_net.clone(),
+                            similarity: rename.similarity,
+                        });
+                        (&self.nets_by_name[&rename.old_net], right)
+                    }
+                    None => {
+                        changes.push(NetChange::Added {
+                            net: name.clone(),
+                            connections: sorted_connections(net_connections(right).into_iter()),
+                        });
+                        continue;
+                    }
+                },
+                (None, None) => unreachable!(),
+            };
+
+            let old_type = left.as_deref().net_type.clone();
+            let new_type = right.as_deref().net_type.clone();
+            if old_type != new_type {
+                changes.push(NetChange::NetTypeChanged {
+                    net: name.clone(),
+                    old: old_type,
+                    new: new_type,
+                });
+            }
+
+            let left_connections = net_connections(left);
+            let right_connections = net_connections(right);
+
+            for connection in
+                sorted_connections(left_connections.difference(&right_connections).cloned())
+            {
+                changes.push(NetChange::Disconnected {
+                    net: name.clone(),
+                    connection,
+                });
+            }
+
+            for connection in
+                sorted_connections(right_connections.difference(&left_connections).cloned())
+            {
+                changes.push(NetChange::Connected {
+                    net: name.clone(),
+                    connection,
+                });
+            }
+        }
+
+        changes
+    }
+}
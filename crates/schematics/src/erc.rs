@@ -0,0 +1,359 @@
+use std::collections::{BTreeMap, HashSet};
+
+use natord::compare;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::ComponentRef,
+    diff::Connection,
+    net::NetRef,
+    part::{PinType, PortRef, FOOTPRINT_KEY},
+    Schematic,
+};
+
+/// The property KiCad uses for a component's footprint.
+const KICAD_FOOTPRINT_KEY: &str = "Footprint";
+
+/// KiCad gives every pin that isn't connected to anything its own net, named
+/// e.g. `unconnected-(U1-NC-Pad5)`.
+const KICAD_UNCONNECTED_PREFIX: &str = "unconnected-";
+
+/// An electrical rule that can be checked against a schematic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// A net that only connects a single port, which is usually a typo in a
+    /// label.
+    SinglePinNet,
+    /// A port that isn't connected to any other port.
+    UnconnectedPort,
+    /// A component with no footprint, which can't be placed on a board.
+    MissingFootprint,
+    /// A net with power input pins, but no power output pin to drive it.
+    PowerNetWithoutDriver,
+    /// A net connecting several outputs, which will fight each other.
+    ShortedOutputs,
+}
+
+impl Rule {
+    pub const ALL: &'static [Rule] = &[
+        Rule::SinglePinNet,
+        Rule::UnconnectedPort,
+        Rule::MissingFootprint,
+        Rule::PowerNetWithoutDriver,
+        Rule::ShortedOutputs,
+    ];
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::SinglePinNet => Severity::Warning,
+            Rule::UnconnectedPort => Severity::Warning,
+            Rule::MissingFootprint => Severity::Error,
+            Rule::PowerNetWithoutDriver => Severity::Warning,
+            Rule::ShortedOutputs => Severity::Error,
+        }
+    }
+
+    /// The name of the rule, as used in configuration files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::SinglePinNet => "single_pin_net",
+            Rule::UnconnectedPort => "unconnected_port",
+            Rule::MissingFootprint => "missing_footprint",
+            Rule::PowerNetWithoutDriver => "power_net_without_driver",
+            Rule::ShortedOutputs => "shorted_outputs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The rule isn't checked.
+    Off,
+    Warning,
+    Error,
+}
+
+/// Configures which rules are checked, and how seriously their violations
+/// are taken. Rules that aren't listed use their default severity.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErcConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<Rule, Severity>,
+}
+
+impl ErcConfig {
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or(rule.default_severity())
+    }
+}
+
+/// A single violation of a rule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<Connection>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ErcReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ErcReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+fn is_kicad_unconnected(net: &NetRef) -> bool {
+    net.as_deref().name.starts_with(KICAD_UNCONNECTED_PREFIX)
+}
+
+fn is_no_connect(port: &PortRef) -> bool {
+    port.as_deref().pin_type == PinType::NoConnect
+}
+
+/// Returns the connections of a net whose pins have one of the given types,
+/// in natural order.
+fn connections_of_type(net: &NetRef, pin_types: &[PinType]) -> Vec<Connection> {
+    let mut connections: Vec<Connection> = net
+        .as_deref()
+        .connections
+        .iter()
+        .filter(|(_, p)| pin_types.contains(&p.as_deref().pin_type))
+        .map(|(c, p)| Connection::new(c, p))
+        .collect();
+    connections.sort_by(|a, b| {
+        compare(&a.component, &b.component)
+            .then_with(|| compare(&a.terminal_identifier, &b.terminal_identifier))
+    });
+    connections
+}
+
+fn sorted_nets(schematic: &Schematic) -> Vec<&NetRef> {
+    let mut nets: Vec<&NetRef> = schematic.nets_iter().collect();
+    nets.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+    nets
+}
+
+fn sorted_components(schematic: &Schematic) -> Vec<&ComponentRef> {
+    let mut components: Vec<&ComponentRef> = schematic.components_iter().collect();
+    components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+    components
+}
+
+fn sorted_ports(component: &ComponentRef) -> Vec<PortRef> {
+    let mut ports: Vec<PortRef> = component
+        .as_deref()
+        .part
+        .as_deref()
+        .ports_by_terminal_identifier
+        .values()
+        .cloned()
+        .collect();
+    ports.sort_by(|a, b| {
+        compare(
+            &a.as_deref().terminal_identifier,
+            &b.as_deref().terminal_identifier,
+        )
+    });
+    ports
+}
+
+fn check_single_pin_nets(schematic: &Schematic) -> Vec<Diagnostic> {
+    sorted_nets(schematic)
+        .into_iter()
+        .filter(|net| {
+            let net_ref = net.as_deref();
+            net_ref.connections.len() == 1
+                && !is_kicad_unconnected(net)
+                && !net_ref.connections.iter().all(|(_, p)| is_no_connect(p))
+        })
+        .map(|net| {
+            let net = net.as_deref();
+            let connection = net
+                .connections
+                .iter()
+                .map(|(c, p)| Connection::new(c, p))
+                .next()
+                .unwrap();
+            Diagnostic {
+                rule: Rule::SinglePinNet,
+                severity: Severity::Off,
+                message: format!("Net {} only connects {}", net.name, connection),
+                net: Some(net.name.clone()),
+                component: None,
+                connections: vec![connection],
+            }
+        })
+        .collect()
+}
+
+fn check_unconnected_ports(schematic: &Schematic) -> Vec<Diagnostic> {
+    // Ports count as connected when they share a net with another port.
+    let connected: HashSet<Connection> = schematic
+        .nets_iter()
+        .filter(|net| net.as_deref().connections.len() > 1)
+        .flat_map(|net| {
+            net.as_deref()
+                .connections
+                .iter()
+                .map(|(c, p)| Connection::new(c, p))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut diagnostics = vec![];
+    for component in sorted_components(schematic) {
+        for port in sorted_ports(component) {
+            let connection = Connection::new(component, &port);
+            if connected.contains(&connection) || is_no_connect(&port) {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic {
+                rule: Rule::UnconnectedPort,
+                severity: Severity::Off,
+                message: format!(
+                    "Port {} ({}) is not connected",
+                    connection,
+                    port.as_deref().signal
+                ),
+                net: None,
+                component: Some(connection.component.clone()),
+                connections: vec![connection],
+            });
+        }
+    }
+    diagnostics
+}
+
+fn check_missing_footprints(schematic: &Schematic) -> Vec<Diagnostic> {
+    sorted_components(schematic)
+        .into_iter()
+        .filter(|component| {
+            let component = component.as_deref();
+            [KICAD_FOOTPRINT_KEY, FOOTPRINT_KEY]
+                .iter()
+                .all(|key| component.get_metadata(key).unwrap_or_default().is_empty())
+        })
+        .map(|component| {
+            let name = component.as_deref().name.clone();
+            Diagnostic {
+                rule: Rule::MissingFootprint,
+                severity: Severity::Off,
+                message: format!("Component {} has no footprint", name),
+                net: None,
+                component: Some(name),
+                connections: vec![],
+            }
+        })
+        .collect()
+}
+
+fn check_power_nets_without_driver(schematic: &Schematic) -> Vec<Diagnostic> {
+    sorted_nets(schematic)
+        .into_iter()
+        .filter_map(|net| {
+            let inputs = connections_of_type(net, &[PinType::PowerIn]);
+            let drivers = connections_of_type(net, &[PinType::PowerOut]);
+            if inputs.is_empty() || !drivers.is_empty() {
+                return None;
+            }
+
+            let name = net.as_deref().name.clone();
+            Some(Diagnostic {
+                rule: Rule::PowerNetWithoutDriver,
+                severity: Severity::Off,
+                message: format!(
+                    "Net {} powers {} but has no power output driving it",
+                    name,
+                    join(&inputs)
+                ),
+                net: Some(name),
+                component: None,
+                connections: inputs,
+            })
+        })
+        .collect()
+}
+
+fn check_shorted_outputs(schematic: &Schematic) -> Vec<Diagnostic> {
+    sorted_nets(schematic)
+        .into_iter()
+        .filter_map(|net| {
+            let outputs = connections_of_type(net, &[PinType::Output, PinType::PowerOut]);
+            if outputs.len() < 2 {
+                return None;
+            }
+
+            let name = net.as_deref().name.clone();
+            Some(Diagnostic {
+                rule: Rule::ShortedOutputs,
+                severity: Severity::Off,
+                message: format!("Net {} connects the outputs {}", name, join(&outputs)),
+                net: Some(name),
+                component: None,
+                connections: outputs,
+            })
+        })
+        .collect()
+}
+
+fn join(connections: &[Connection]) -> String {
+    connections
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Schematic {
+    /// Run the electrical rules check, returning the violations of every rule
+    /// that isn't turned off in `config`, grouped by rule.
+    pub fn erc(&self, config: &ErcConfig) -> ErcReport {
+        let mut report = ErcReport::default();
+
+        for rule in Rule::ALL.iter() {
+            let severity = config.severity(*rule);
+            if severity == Severity::Off {
+                continue;
+            }
+
+            let diagnostics = match rule {
+                Rule::SinglePinNet => check_single_pin_nets(self),
+                Rule::UnconnectedPort => check_unconnected_ports(self),
+                Rule::MissingFootprint => check_missing_footprints(self),
+                Rule::PowerNetWithoutDriver => check_power_nets_without_driver(self),
+                Rule::ShortedOutputs => check_shorted_outputs(self),
+            };
+
+            report.diagnostics.extend(
+                diagnostics
+                    .into_iter()
+                    .map(|d| Diagnostic { severity, ..d }),
+            );
+        }
+
+        report
+    }
+}
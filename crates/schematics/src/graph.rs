@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+
+use natord::compare;
+
+use crate::{
+    component::ComponentRef,
+    net::{NetRef, NetType},
+    Schematic, SchematicError,
+};
+
+/// Which part of a schematic to draw.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphFilter {
+    /// Only draw the components placed on this sheet and the sheets below
+    /// it, e.g. `["Power", "LDO"]`, and the nets they're connected to.
+    pub sheet: Option<Vec<String>>,
+
+    /// Only draw the neighbourhood of this net: the components connected to
+    /// it, and the other nets those are connected to.
+    pub net: Option<String>,
+}
+
+/// The fill colour of a net, by its type.
+fn net_color(net_type: &NetType) -> &'static str {
+    match net_type {
+        NetType::Unknown => "#ffffff",
+        NetType::Power => "#f4a6a6",
+        NetType::Ground => "#c8c8c8",
+        NetType::Digital => "#a6c8f4",
+        NetType::Analog => "#b4e0a6",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Quote a string as a DOT identifier.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+fn component_id(name: &str) -> String {
+    quote(&format!("component:{}", name))
+}
+
+fn net_id(name: &str) -> String {
+    quote(&format!("net:{}", name))
+}
+
+fn nets_of(schematic: &Schematic, component: &ComponentRef) -> Vec<NetRef> {
+    schematic
+        .nets_iter()
+        .filter(|net| {
+            net.as_deref()
+                .connections
+                .iter()
+                .any(|(c, _)| c == component)
+        })
+        .cloned()
+        .collect()
+}
+
+impl Schematic {
+    /// Render the schematic as an undirected graph in Graphviz's DOT
+    /// language, with a node per component and per net, and an edge labelled
+    /// with the pin for each connection. Nets are coloured by their type.
+    pub fn to_dot(&self, filter: &GraphFilter) -> Result<String, SchematicError> {
+        let mut components: BTreeSet<String> = self
+            .components_iter()
+            .filter(|c| match filter.sheet.as_ref() {
+                Some(sheet) => c.as_deref().sheet_path().starts_with(sheet),
+                None => true,
+            })
+            .map(|c| c.as_deref().name.clone())
+            .collect();
+
+        if let Some(name) = filter.net.as_ref() {
+            let net = self
+                .get_net(name)
+                .ok_or_else(|| SchematicError::NameNotFound(name.clone()))?;
+            let neighbours: BTreeSet<String> = net
+                .as_deref()
+                .connections
+                .iter()
+                .map(|(c, _)| c.as_deref().name.clone())
+                .collect();
+            components.retain(|c| neighbours.contains(c));
+        }
+
+        // Every net connected to a component that's drawn.
+        let mut nets: BTreeSet<String> = BTreeSet::new();
+        for name in components.iter() {
+            let component = self.get_component(name).unwrap();
+            nets.extend(nets_of(self, &component).iter().map(|n| n.name()));
+        }
+        if filter.sheet.is_none() && filter.net.is_none() {
+            nets.extend(self.nets_iter().map(|n| n.name()));
+        }
+
+        let mut sorted_components: Vec<&String> = components.iter().collect();
+        sorted_components.sort_by(|a, b| compare(a, b));
+        let mut nets: Vec<String> = nets.into_iter().collect();
+        nets.sort_by(|a, b| compare(a, b));
+
+        let mut out = String::from("graph schematic {\n");
+        out.push_str("  graph [rankdir=LR];\n");
+        out.push_str("  node [fontname=\"Helvetica\", fontsize=10];\n");
+        out.push_str("  edge [fontname=\"Helvetica\", fontsize=8];\n");
+
+        for name in sorted_components {
+            let component = self.get_component(name).unwrap();
+            out.push_str(&format!(
+                "  {} [label=\"{}\\n{}\", shape=box];\n",
+                component_id(name),
+                escape(name),
+                escape(&component.as_deref().part.as_deref().name)
+            ));
+        }
+
+        for name in nets.iter() {
+            let net = self.get_net(name).unwrap();
+            let net = net.as_deref();
+            out.push_str(&format!(
+                "  {} [label={}, shape=ellipse, style=filled, fillcolor={}];\n",
+                net_id(name),
+                quote(name),
+                quote(net_color(&net.net_type))
+            ));
+
+            let mut connections: Vec<(String, String)> = net
+                .connections
+                .iter()
+                .map(|(c, p)| {
+                    (
+                        c.as_deref().name.clone(),
+                        p.as_deref().terminal_identifier.clone(),
+                    )
+                })
+                .filter(|(c, _)| components.contains(c))
+                .collect();
+            connections.sort_by(|a, b| compare(&a.0, &b.0).then_with(|| compare(&a.1, &b.1)));
+            for (component, pin) in connections {
+                out.push_str(&format!(
+                    "  {} -- {} [label={}];\n",
+                    component_id(&component),
+                    net_id(name),
+                    quote(&pin)
+                ));
+            }
+        }
+
+        out.push_str("}\n");
+        Ok(out)
+    }
+}
@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate derive_builder;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub use std::fmt::Display;
 
@@ -11,11 +11,19 @@ use net::{Net, NetRef};
 use part::{Part, PartRef};
 use thiserror::Error;
 
+pub mod bom;
 pub mod component;
+pub mod diff;
 pub mod edatasheet;
+pub mod erc;
+pub mod graph;
 pub mod net;
+pub mod net_inference;
 pub mod part;
+pub mod query;
+pub mod serialization;
 pub mod standard_library;
+pub mod value;
 
 /// `Schematic` encodes the logical representation of an electrical design. It
 /// does not contain any visual information to support manual layout or
@@ -51,6 +59,14 @@ pub enum SchematicError {
     UninitializedField(String),
     #[error("Normalization error: {0}")]
     NormalizationError(#[from] NormalizationError),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+    #[error("Invalid pin type: {0}")]
+    InvalidPinType(String),
+    #[error("Invalid value: {0}")]
+    InvalidValue(String),
+    #[error("Invalid rename threshold: {0}, expected a fraction from 0 to 1")]
+    InvalidRenameThreshold(f64),
 }
 
 #[derive(Error, Debug)]
@@ -88,6 +104,12 @@ impl Schematic {
         Ok(part_ref)
     }
 
+    /// Remove a part. Components which use the part keep their reference to
+    /// it, so this is only meant for parts which aren't used yet.
+    pub fn remove_part(&mut self, name: &str) -> Option<PartRef> {
+        self.parts_by_name.remove(name)
+    }
+
     pub fn add_component(&mut self, component: Component) -> Result<ComponentRef, SchematicError> {
         let name = component.name.clone();
         if self.components_by_name.contains_key(&name) {
@@ -181,9 +203,10 @@ impl Schematic {
         normalizer_fn: impl Fn(&str) -> Result<String, NormalizationError>,
     ) -> Result<HashMap<String, String>, SchematicError> {
         let mut new_names = HashMap::new();
+        let mut taken = HashSet::new();
         for name in iter {
             let new_name = normalizer_fn(name)?;
-            if new_names.contains_key(&new_name) {
+            if !taken.insert(new_name.clone()) {
                 return Err(NormalizationError::NameConflict(new_name).into());
             }
             new_names.insert(name.clone(), new_name);
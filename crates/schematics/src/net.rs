@@ -6,6 +6,8 @@ use std::{
     rc::Rc,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{component::ComponentRef, part::PortRef, SchematicError};
 
 #[derive(Debug, Clone)]
@@ -43,7 +45,8 @@ impl PartialEq for NetRef {
 
 impl Eq for NetRef {}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetType {
     Unknown,
     Power,
@@ -52,12 +55,28 @@ pub enum NetType {
     Analog,
 }
 
+impl NetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetType::Unknown => "unknown",
+            NetType::Power => "power",
+            NetType::Ground => "ground",
+            NetType::Digital => "digital",
+            NetType::Analog => "analog",
+        }
+    }
+}
+
 #[derive(Debug, Builder)]
 #[builder(build_fn(error = "SchematicError"))]
 pub struct Net {
     pub name: String,
     #[builder(default = "NetType::Unknown")]
     pub net_type: NetType,
+    /// The power symbol that names this net, e.g. `power:GND`, if the source
+    /// format has them.
+    #[builder(default = "None")]
+    pub power_symbol: Option<String>,
     #[builder(default = "HashSet::new()")]
     pub connections: HashSet<(ComponentRef, PortRef)>,
 }
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use natord::compare;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diff::Connection,
+    net::{NetRef, NetType},
+    part::PinType,
+    Schematic, SchematicError,
+};
+
+/// Prefixes of the names KiCad gives to nets without a label, e.g.
+/// `Net-(R1-Pad2)`. These are named after a pin, so they say nothing about
+/// the net itself.
+const KICAD_GENERATED_PREFIXES: &[&str] = &["Net-(", "unconnected-("];
+
+const GROUND_NAMES: &[&str] = &["0V", "EARTH", "CHASSIS"];
+const GROUND_PREFIXES: &[&str] = &["GND", "VSS"];
+const POWER_NAMES: &[&str] = &["V+"];
+const POWER_PREFIXES: &[&str] = &["VCC", "VDD", "VBAT", "VBUS", "VIN", "VSYS", "VIO", "VMOT"];
+const ANALOG_PREFIXES: &[&str] = &["AIN", "ADC", "DAC", "VREF"];
+const DIGITAL_NAMES: &[&str] = &[
+    "SDA", "SCL", "SCK", "SCLK", "MOSI", "MISO", "COPI", "CIPO", "CS", "NSS", "TX", "RX", "TXD",
+    "RXD", "RTS", "CTS", "SWDIO", "SWCLK", "SWO", "TCK", "TMS", "TDI", "TDO", "RST", "NRST",
+    "RESET", "CLK", "INT", "IRQ",
+];
+const DIGITAL_PREFIXES: &[&str] = &["GPIO"];
+
+/// Pin types which only carry logic signals.
+const LOGIC_PIN_TYPES: &[PinType] = &[
+    PinType::Input,
+    PinType::Output,
+    PinType::Bidirectional,
+    PinType::Tristate,
+    PinType::OpenCollector,
+    PinType::OpenEmitter,
+];
+
+/// Net types set by the user, which take precedence over anything inferred.
+/// Stored as JSON, e.g. `{"nets": {"VMOTOR": "power"}}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetTypeOverrides {
+    #[serde(default)]
+    pub nets: BTreeMap<String, NetType>,
+}
+
+/// The kind of evidence a net type was decided from, strongest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetTypeSource {
+    Override,
+    PowerSymbol,
+    PinTypes,
+    Name,
+    /// There was no evidence, so the net type is `Unknown`.
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetTypeDecision {
+    pub net: String,
+    pub net_type: NetType,
+    pub source: NetTypeSource,
+    pub reason: String,
+}
+
+/// How the type of every net was decided, in natural order of net name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NetTypeReport {
+    pub nets: Vec<NetTypeDecision>,
+}
+
+/// The parts of a net name worth looking at, e.g. `VCC` and `3V3` for
+/// `/power/VCC_3V3`, or `vcc` for the Atopile net `power.vcc`.
+fn name_tokens(name: &str) -> Vec<String> {
+    if KICAD_GENERATED_PREFIXES.iter().any(|p| name.starts_with(p)) {
+        return vec![];
+    }
+
+    let leaf = name.rsplit('/').next().unwrap_or(name);
+    let mut tokens = vec![leaf.to_uppercase()];
+    // Atopile nets are named by their path, but KiCad names may contain
+    // dots too, e.g. `+3.3V`.
+    if let Some((_, last)) = leaf.rsplit_once('.') {
+        tokens.push(last.to_uppercase());
+    }
+    let words: Vec<String> = tokens
+        .iter()
+        .flat_map(|t| t.split(['_', '-']).map(|w| w.to_string()))
+        .collect();
+    tokens.extend(words);
+    tokens.retain(|t| !t.is_empty());
+    tokens
+}
+
+/// Whether a name is a voltage, such as `+3V3`, `3.3V`, `-12V` or `P5V` (as
+/// normalized for Atopile).
+fn is_voltage(token: &str) -> bool {
+    let token = token.trim_start_matches(['+', '-', 'P', 'N']);
+    let Some((volts, fraction)) = token.split_once('V') else {
+        return false;
+    };
+    let volts = volts.replace('.', "");
+    !volts.is_empty()
+        && volts.chars().all(|c| c.is_ascii_digit())
+        && fraction.chars().all(|c| c.is_ascii_digit())
+}
+
+fn matches(tokens: &[String], names: &[&str], prefixes: &[&str]) -> Option<String> {
+    tokens
+        .iter()
+        .find(|t| names.contains(&t.as_str()) || prefixes.iter().any(|p| t.starts_with(p)))
+        .cloned()
+}
+
+fn ground_token(tokens: &[String]) -> Option<String> {
+    matches(tokens, GROUND_NAMES, GROUND_PREFIXES)
+        .or_else(|| tokens.iter().find(|t| t.ends_with("GND")).cloned())
+}
+
+/// Classify a net by its name alone.
+fn type_from_name(name: &str) -> Option<(NetType, String)> {
+    let tokens = name_tokens(name);
+    if let Some(token) = ground_token(&tokens) {
+        return Some((NetType::Ground, token));
+    }
+    if let Some(token) = matches(&tokens, POWER_NAMES, POWER_PREFIXES)
+        .or_else(|| tokens.iter().find(|t| is_voltage(t)).cloned())
+    {
+        return Some((NetType::Power, token));
+    }
+    if let Some(token) = matches(&tokens, &[], ANALOG_PREFIXES) {
+        return Some((NetType::Analog, token));
+    }
+    if let Some(token) = matches(&tokens, DIGITAL_NAMES, DIGITAL_PREFIXES) {
+        return Some((NetType::Digital, token));
+    }
+    None
+}
+
+/// Power and ground nets look the same to power symbols and pins, so tell
+/// them apart by name.
+fn power_or_ground(names: &[&str]) -> NetType {
+    match names
+        .iter()
+        .any(|n| ground_token(&name_tokens(n)).is_some())
+    {
+        true => NetType::Ground,
+        false => NetType::Power,
+    }
+}
+
+fn connections(net: &NetRef, pin_types: &[PinType]) -> Vec<Connection> {
+    let mut connections: Vec<Connection> = net
+        .as_deref()
+        .connections
+        .iter()
+        .filter(|(_, p)| pin_types.contains(&p.as_deref().pin_type))
+        .map(|(c, p)| Connection::new(c, p))
+        .collect();
+    connections.sort_by(|a, b| {
+        compare(&a.component, &b.component)
+            .then_with(|| compare(&a.terminal_identifier, &b.terminal_identifier))
+    });
+    connections
+}
+
+fn join(connections: &[Connection]) -> String {
+    connections
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn decide(net: &NetRef, overrides: &NetTypeOverrides) -> (NetType, NetTypeSource, String) {
+    let name = net.name();
+
+    if let Some(net_type) = overrides.nets.get(&name) {
+        return (
+            net_type.clone(),
+            NetTypeSource::Override,
+            "set in the overrides".to_string(),
+        );
+    }
+
+    if let Some(symbol) = net.as_deref().power_symbol.clone() {
+        return (
+            power_or_ground(&[&name, &symbol]),
+            NetTypeSource::PowerSymbol,
+            format!("connected to the power symbol {}", symbol),
+        );
+    }
+
+    let power_pins = connections(net, &[PinType::PowerIn, PinType::PowerOut]);
+    if !power_pins.is_empty() {
+        // Unlabelled nets are named after a pin, so also look at the names of
+        // the power pins, e.g. a `GND` pin.
+        let signals: Vec<String> = net
+            .as_deref()
+            .connections
+            .iter()
+            .filter(|(_, p)| matches!(p.as_deref().pin_type, PinType::PowerIn | PinType::PowerOut))
+            .map(|(_, p)| p.as_deref().signal.clone())
+            .collect();
+        let mut names = vec![name.as_str()];
+        names.extend(signals.iter().map(|s| s.as_str()));
+        return (
+            power_or_ground(&names),
+            NetTypeSource::PinTypes,
+            format!("connects the power pins {}", join(&power_pins)),
+        );
+    }
+
+    let logic_pins = connections(net, LOGIC_PIN_TYPES);
+    let pin_count = net.as_deref().connections.len();
+    if !logic_pins.is_empty() && logic_pins.len() == pin_count {
+        return (
+            NetType::Digital,
+            NetTypeSource::PinTypes,
+            format!("only connects the logic pins {}", join(&logic_pins)),
+        );
+    }
+
+    if let Some((net_type, token)) = type_from_name(&name) {
+        return (
+            net_type,
+            NetTypeSource::Name,
+            format!("the name contains {}", token),
+        );
+    }
+
+    (
+        NetType::Unknown,
+        NetTypeSource::None,
+        "no evidence".to_string(),
+    )
+}
+
+impl Schematic {
+    /// Set the type of every net from the evidence available: `overrides`
+    /// first, then power symbols, the types of the connected pins and
+    /// finally the net's name. Returns how each type was decided.
+    pub fn infer_net_types(
+        &mut self,
+        overrides: &NetTypeOverrides,
+    ) -> Result<NetTypeReport, SchematicError> {
+        if let Some(name) = overrides.nets.keys().find(|n| self.get_net(n).is_none()) {
+            return Err(SchematicError::NameNotFound(name.clone()));
+        }
+
+        let mut nets: Vec<NetRef> = self.nets_iter().cloned().collect();
+        nets.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+        let mut report = NetTypeReport::default();
+        for net in nets {
+            let (net_type, source, reason) = decide(&net, overrides);
+            net.as_deref_mut().net_type = net_type.clone();
+            report.nets.push(NetTypeDecision {
+                net: net.name(),
+                net_type,
+                source,
+                reason,
+            });
+        }
+
+        Ok(report)
+    }
+}
@@ -1,11 +1,14 @@
 use crate::SchematicError;
 use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
     collections::HashMap,
+    fmt,
     hash::Hash,
     ops::{Deref, DerefMut},
     rc::Rc,
+    str::FromStr,
 };
 
 #[cfg(feature = "edatasheet")]
@@ -78,7 +81,16 @@ impl Part {
 
 impl PartBuilder {
     pub fn port(&mut self, terminal_identifier: &str, signal: &str) -> &mut Self {
-        let port = Port::new(terminal_identifier, signal);
+        self.port_with_type(terminal_identifier, signal, PinType::default())
+    }
+
+    pub fn port_with_type(
+        &mut self,
+        terminal_identifier: &str,
+        signal: &str,
+        pin_type: PinType,
+    ) -> &mut Self {
+        let port = Port::new(terminal_identifier, signal).with_pin_type(pin_type);
         let ports = self
             .ports_by_terminal_identifier
             .get_or_insert_with(HashMap::new);
@@ -125,10 +137,81 @@ impl PartialEq for PortRef {
 
 impl Eq for PortRef {}
 
+/// The electrical type of a pin, as used by the electrical rules check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinType {
+    Input,
+    Output,
+    Bidirectional,
+    Tristate,
+    Passive,
+    PowerIn,
+    PowerOut,
+    OpenCollector,
+    OpenEmitter,
+    NoConnect,
+    /// KiCad's `free` pins, which can be connected to anything.
+    Free,
+    #[default]
+    Unspecified,
+}
+
+impl PinType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PinType::Input => "input",
+            PinType::Output => "output",
+            PinType::Bidirectional => "bidirectional",
+            PinType::Tristate => "tristate",
+            PinType::Passive => "passive",
+            PinType::PowerIn => "power_in",
+            PinType::PowerOut => "power_out",
+            PinType::OpenCollector => "open_collector",
+            PinType::OpenEmitter => "open_emitter",
+            PinType::NoConnect => "no_connect",
+            PinType::Free => "free",
+            PinType::Unspecified => "unspecified",
+        }
+    }
+}
+
+impl fmt::Display for PinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for PinType {
+    type Err = SchematicError;
+
+    /// Parses the names returned by `as_str`, as well as the spellings used by
+    /// KiCad 5 and later (e.g. `tri_state`, `BiDi`, `openCol`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "input" | "in" => Ok(PinType::Input),
+            "output" | "out" => Ok(PinType::Output),
+            "bidirectional" | "BiDi" => Ok(PinType::Bidirectional),
+            "tristate" | "tri_state" | "3state" => Ok(PinType::Tristate),
+            "passive" => Ok(PinType::Passive),
+            "power_in" => Ok(PinType::PowerIn),
+            "power_out" => Ok(PinType::PowerOut),
+            "open_collector" | "openCol" => Ok(PinType::OpenCollector),
+            "open_emitter" | "openEm" => Ok(PinType::OpenEmitter),
+            "no_connect" | "NotConnected" => Ok(PinType::NoConnect),
+            "free" => Ok(PinType::Free),
+            "unspecified" | "unspc" => Ok(PinType::Unspecified),
+            _ => Err(SchematicError::InvalidPinType(s.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Builder, Clone)]
 pub struct Port {
     pub terminal_identifier: String,
     pub signal: String,
+    #[builder(default)]
+    pub pin_type: PinType,
 }
 
 impl Port {
@@ -136,6 +219,12 @@ impl Port {
         Self {
             terminal_identifier: terminal_identifier.to_string(),
             signal: signal.to_string(),
+            pin_type: PinType::default(),
         }
     }
+
+    pub fn with_pin_type(mut self, pin_type: PinType) -> Self {
+        self.pin_type = pin_type;
+        self
+    }
 }
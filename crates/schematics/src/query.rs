@@ -0,0 +1,629 @@
+//! A small language for querying the nets, components and pins of a
+//! schematic, e.g.:
+//!
+//! - `nets where connects(U1.SDA)`
+//! - `components where part ~ "STM32*" and sheet = "MCU"`
+//! - `pins of U3 unconnected`
+//!
+//! A query names what to list, optionally followed by `where` and a filter.
+//! Filters compare a field with `=`, `!=` or `~` (a glob, where `*` matches
+//! any text and `?` a single character), or check connectivity with
+//! `connects(...)`, and are combined with `and`, `or`, `not` and parentheses.
+
+use std::{collections::BTreeMap, fmt};
+
+use natord::compare;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    component::ComponentRef, diff::Connection, net::NetRef, part::PortRef, standard_library,
+    Schematic,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("Syntax error at character {position}: {message}")]
+    Syntax { position: usize, message: String },
+    #[error("Unknown field {field} for {target}")]
+    UnknownField { field: String, target: String },
+    #[error("Name not found: {0}")]
+    NameNotFound(String),
+}
+
+/// What a query lists.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Nets,
+    Components,
+    /// The pins of a component, optionally only those that are (or aren't)
+    /// connected to another pin.
+    Pins {
+        component: String,
+        connected: Option<bool>,
+    },
+}
+
+impl Target {
+    /// The fields that can be filtered on, which are also the columns of the
+    /// result.
+    fn fields(&self) -> &'static [&'static str] {
+        match self {
+            Target::Nets => &["name", "type", "pins", "connections"],
+            Target::Components => &["name", "part", "value", "sheet"],
+            Target::Pins { .. } => &["component", "pin", "signal", "type", "net"],
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Nets => write!(f, "nets"),
+            Target::Components => write!(f, "components"),
+            Target::Pins { .. } => write!(f, "pins"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    /// Matches a glob, e.g. `STM32*`.
+    Glob,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: String,
+        op: Op,
+        value: String,
+    },
+    /// For nets and pins, whether they're connected to a pin, given as
+    /// `U1.SDA` (by pin number or signal), or to any pin of a component,
+    /// given as `U1`. For components, whether they're connected to a net.
+    Connects(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub target: Target,
+    pub filter: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// A quoted string, which is never a keyword.
+    String(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+fn syntax_error(position: usize, message: impl Into<String>) -> QueryError {
+    QueryError::Syntax {
+        position,
+        message: message.into(),
+    }
+}
+
+/// Split a query into tokens, each with the position it starts at.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Op(Op::Eq),
+            '~' => Token::Op(Op::Glob),
+            '!' => match chars.next() {
+                Some((_, '=')) => Token::Op(Op::Ne),
+                _ => return Err(syntax_error(i, "expected !=")),
+            },
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => value.push(c),
+                            None => return Err(syntax_error(i, "unterminated string")),
+                        },
+                        Some((_, c)) => value.push(c),
+                        None => return Err(syntax_error(i, "unterminated string")),
+                    }
+                }
+                Token::String(value)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.peek() {
+                    if c.is_whitespace() || "()=!~\"".contains(*c) {
+                        break;
+                    }
+                    word.push(*c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push((i, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// The length of the query, where errors at its end are reported.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map(|(i, _)| *i)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, message: impl Into<String>) -> QueryError {
+        syntax_error(self.position(), message)
+    }
+
+    /// Whether the next token is the keyword `keyword`, consuming it if so.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        match self.keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected {}", keyword))),
+        }
+    }
+
+    /// A name or value, quoted or not.
+    fn value(&mut self, what: &str) -> Result<String, QueryError> {
+        match self.peek().cloned() {
+            Some(Token::Word(value)) | Some(Token::String(value)) => {
+                self.next += 1;
+                Ok(value)
+            }
+            _ => Err(self.error(format!("expected {}", what))),
+        }
+    }
+
+    fn target(&mut self) -> Result<Target, QueryError> {
+        if self.keyword("nets") {
+            return Ok(Target::Nets);
+        }
+        if self.keyword("components") {
+            return Ok(Target::Components);
+        }
+        if self.keyword("pins") {
+            self.expect_keyword("of")?;
+            let component = self.value("a component")?;
+            let connected = if self.keyword("connected") {
+                Some(true)
+            } else if self.keyword("unconnected") {
+                Some(false)
+            } else {
+                None
+            };
+            return Ok(Target::Pins {
+                component,
+                connected,
+            });
+        }
+        Err(self.error("expected nets, components or pins"))
+    }
+
+    fn or(&mut self, target: &Target) -> Result<Expr, QueryError> {
+        let mut expr = self.and(target)?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and(target)?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self, target: &Target) -> Result<Expr, QueryError> {
+        let mut expr = self.unary(target)?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary(target)?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self, target: &Target) -> Result<Expr, QueryError> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary(target)?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.next += 1;
+            let expr = self.or(target)?;
+            if self.peek() != Some(&Token::Close) {
+                return Err(self.error("expected )"));
+            }
+            self.next += 1;
+            return Ok(expr);
+        }
+        if self.keyword("connects") {
+            if self.peek() != Some(&Token::Open) {
+                return Err(self.error("expected ("));
+            }
+            self.next += 1;
+            let value = self.value("a net, component or pin")?;
+            if self.peek() != Some(&Token::Close) {
+                return Err(self.error("expected )"));
+            }
+            self.next += 1;
+            return Ok(Expr::Connects(value));
+        }
+
+        let field = match self.peek().cloned() {
+            Some(Token::Word(field)) => field.to_lowercase(),
+            _ => return Err(self.error("expected a field or connects(...)")),
+        };
+        // Components can also be filtered on any of their metadata.
+        if !target.fields().contains(&field.as_str()) && *target != Target::Components {
+            return Err(QueryError::UnknownField {
+                field,
+                target: target.to_string(),
+            });
+        }
+        self.next += 1;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Err(self.error("expected =, != or ~")),
+        };
+        self.next += 1;
+        let value = self.value("a value")?;
+        Ok(Expr::Compare { field, op, value })
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            next: 0,
+            end: text.len(),
+        };
+        let target = parser.target()?;
+        let filter = match parser.keyword("where") {
+            true => Some(parser.or(&target)?),
+            false => None,
+        };
+        if parser.peek().is_some() {
+            return Err(parser.error("expected where, and, or or the end of the query"));
+        }
+        Ok(Query { target, filter })
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any text and `?` any
+/// single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Where to resume after the last `*`, if the rest fails to match.
+    let (mut p, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// A net, component or pin being filtered.
+trait Row {
+    /// The value of a field, or `None` if it has none.
+    fn field(&self, name: &str) -> Option<String>;
+
+    fn connects(&self, schematic: &Schematic, target: &str) -> bool;
+
+    fn matches(&self, schematic: &Schematic, expr: &Expr) -> bool {
+        match expr {
+            Expr::And(a, b) => self.matches(schematic, a) && self.matches(schematic, b),
+            Expr::Or(a, b) => self.matches(schematic, a) || self.matches(schematic, b),
+            Expr::Not(a) => !self.matches(schematic, a),
+            Expr::Connects(target) => self.connects(schematic, target),
+            Expr::Compare { field, op, value } => {
+                let actual = self.field(field);
+                match op {
+                    Op::Eq => actual.as_ref() == Some(value),
+                    Op::Ne => actual.as_ref() != Some(value),
+                    Op::Glob => actual.is_some_and(|actual| glob_match(value, &actual)),
+                }
+            }
+        }
+    }
+}
+
+/// Whether `port` of `component` is `target`, given as `U1.SDA`, by number or
+/// signal, or is any pin of a component given as `U1`.
+fn is_pin(component: &ComponentRef, port: &PortRef, target: &str) -> bool {
+    let component = component.as_deref();
+    if component.name == target {
+        return true;
+    }
+    let port = port.as_deref();
+    target.rsplit_once('.').is_some_and(|(name, pin)| {
+        name == component.name && (pin == port.terminal_identifier || pin == port.signal)
+    })
+}
+
+struct NetRow<'a>(&'a NetRef);
+
+impl NetRow<'_> {
+    fn connections(&self) -> Vec<Connection> {
+        let mut connections: Vec<Connection> = self
+            .0
+            .as_deref()
+            .connections
+            .iter()
+            .map(|(c, p)| Connection::new(c, p))
+            .collect();
+        connections.sort_by(|a, b| {
+            compare(&a.component, &b.component)
+                .then_with(|| compare(&a.terminal_identifier, &b.terminal_identifier))
+        });
+        connections
+    }
+}
+
+impl Row for NetRow<'_> {
+    fn field(&self, name: &str) -> Option<String> {
+        let net = self.0.as_deref();
+        match name {
+            "name" => Some(net.name.clone()),
+            "type" => Some(net.net_type.as_str().to_string()),
+            "pins" => Some(net.connections.len().to_string()),
+            "connections" => Some(
+                self.connections()
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            _ => None,
+        }
+    }
+
+    fn connects(&self, _: &Schematic, target: &str) -> bool {
+        self.0
+            .as_deref()
+            .connections
+            .iter()
+            .any(|(c, p)| is_pin(c, p, target))
+    }
+}
+
+struct ComponentRow<'a>(&'a ComponentRef);
+
+impl Row for ComponentRow<'_> {
+    fn field(&self, name: &str) -> Option<String> {
+        let component = self.0.as_deref();
+        match name {
+            "name" => Some(component.name.clone()),
+            "part" => Some(component.part.as_deref().name.clone()),
+            "sheet" => Some(component.sheet_path().last().cloned().unwrap_or_default()),
+            "value" => {
+                let value_key = standard_library::value_key(&component.part.as_deref().name);
+                component
+                    .get_metadata("Value")
+                    .or(value_key.and_then(|key| component.get_metadata(key)))
+            }
+            // Metadata keys are matched as written, or capitalized as in
+            // KiCad, e.g. `footprint` or `mpn`.
+            _ => {
+                let mut capitalized = name.to_string();
+                if let Some(first) = capitalized.get_mut(0..1) {
+                    first.make_ascii_uppercase();
+                }
+                [name.to_string(), capitalized, name.to_uppercase()]
+                    .iter()
+                    .find_map(|key| component.get_metadata(key))
+            }
+        }
+    }
+
+    fn connects(&self, schematic: &Schematic, target: &str) -> bool {
+        schematic
+            .get_net(target)
+            .is_some_and(|net| net.as_deref().connections.iter().any(|(c, _)| c == self.0))
+    }
+}
+
+struct PinRow<'a> {
+    component: &'a ComponentRef,
+    port: PortRef,
+    net: Option<NetRef>,
+}
+
+impl PinRow<'_> {
+    /// Whether the pin shares a net with another pin.
+    fn is_connected(&self) -> bool {
+        self.net
+            .as_ref()
+            .is_some_and(|net| net.as_deref().connections.len() > 1)
+    }
+}
+
+impl Row for PinRow<'_> {
+    fn field(&self, name: &str) -> Option<String> {
+        let port = self.port.as_deref();
+        match name {
+            "component" => Some(self.component.as_deref().name.clone()),
+            "pin" => Some(port.terminal_identifier.clone()),
+            "signal" => Some(port.signal.clone()),
+            "type" => Some(port.pin_type.as_str().to_string()),
+            "net" => self.net.as_ref().map(|net| net.name()),
+            _ => None,
+        }
+    }
+
+    fn connects(&self, _: &Schematic, target: &str) -> bool {
+        self.net.as_ref().is_some_and(|net| {
+            net.as_deref()
+                .connections
+                .iter()
+                .any(|(c, p)| !(c == self.component && *p == self.port) && is_pin(c, p, target))
+        })
+    }
+}
+
+/// The rows a query matched, with a value per column.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl QueryResult {
+    fn new(target: &Target) -> Self {
+        QueryResult {
+            columns: target.fields().iter().map(|f| f.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    fn push(&mut self, row: &dyn Row) {
+        self.rows.push(
+            self.columns
+                .iter()
+                .map(|column| row.field(column).unwrap_or_default())
+                .collect(),
+        );
+    }
+
+    /// The rows as records keyed by column, for JSON output.
+    pub fn records(&self) -> Vec<BTreeMap<String, String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Schematic {
+    /// Run a query, returning the matching nets, components or pins in
+    /// natural order.
+    pub fn query(&self, query: &Query) -> Result<QueryResult, QueryError> {
+        let mut result = QueryResult::new(&query.target);
+        let matches = |row: &dyn Row| match query.filter.as_ref() {
+            Some(filter) => row.matches(self, filter),
+            None => true,
+        };
+
+        match &query.target {
+            Target::Nets => {
+                let mut nets: Vec<&NetRef> = self.nets_iter().collect();
+                nets.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+                for net in nets {
+                    let row = NetRow(net);
+                    if matches(&row) {
+                        result.push(&row);
+                    }
+                }
+            }
+            Target::Components => {
+                let mut components: Vec<&ComponentRef> = self.components_iter().collect();
+                components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+                for component in components {
+                    let row = ComponentRow(component);
+                    if matches(&row) {
+                        result.push(&row);
+                    }
+                }
+            }
+            Target::Pins {
+                component,
+                connected,
+            } => {
+                let component = self
+                    .get_component(component)
+                    .ok_or_else(|| QueryError::NameNotFound(component.clone()))?;
+                let mut ports: Vec<PortRef> = component
+                    .as_deref()
+                    .part
+                    .as_deref()
+                    .ports_by_terminal_identifier
+                    .values()
+                    .cloned()
+                    .collect();
+                ports.sort_by(|a, b| {
+                    compare(
+                        &a.as_deref().terminal_identifier,
+                        &b.as_deref().terminal_identifier,
+                    )
+                });
+                for port in ports {
+                    let net = self
+                        .nets_iter()
+                        .find(|net| {
+                            net.as_deref()
+                                .connections
+                                .iter()
+                                .any(|(c, p)| *c == component && *p == port)
+                        })
+                        .cloned();
+                    let row = PinRow {
+                        component: &component,
+                        port,
+                        net,
+                    };
+                    if connected.is_some_and(|connected| connected != row.is_connected()) {
+                        continue;
+                    }
+                    if matches(&row) {
+                        result.push(&row);
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use natord::compare;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::ComponentBuilder,
+    diff::Connection,
+    net::{NetBuilder, NetType},
+    part::{PartBuilder, PinType},
+    Schematic, SchematicError,
+};
+
+/// The version of the serialized format. Bump this when making a change that
+/// older readers can't handle.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A serializable snapshot of a `Schematic`. Parts, components and nets refer
+/// to each other by name rather than by handle, and every list is sorted in
+/// natural order so that the output is stable.
+///
+/// The edatasheet of a part is not included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedSchematic {
+    pub version: u32,
+    pub parts: Vec<SerializedPart>,
+    pub components: Vec<SerializedComponent>,
+    pub nets: Vec<SerializedNet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedPart {
+    pub name: String,
+    pub ports: Vec<SerializedPort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datasheet_url: Option<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedPort {
+    pub terminal_identifier: String,
+    pub signal: String,
+    #[serde(default, skip_serializing_if = "is_unspecified")]
+    pub pin_type: PinType,
+}
+
+fn is_unspecified(pin_type: &PinType) -> bool {
+    *pin_type == PinType::Unspecified
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedComponent {
+    pub name: String,
+    pub part: String,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SerializedNet {
+    pub name: String,
+    pub net_type: NetType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_symbol: Option<String>,
+    pub connections: Vec<Connection>,
+}
+
+impl From<&Schematic> for SerializedSchematic {
+    fn from(schematic: &Schematic) -> Self {
+        let mut parts: Vec<SerializedPart> = schematic
+            .parts_iter()
+            .map(|part| {
+                let part = part.as_deref();
+                let mut ports: Vec<SerializedPort> = part
+                    .ports_by_terminal_identifier
+                    .values()
+                    .map(|port| {
+                        let port = port.as_deref();
+                        SerializedPort {
+                            terminal_identifier: port.terminal_identifier.clone(),
+                            signal: port.signal.clone(),
+                            pin_type: port.pin_type,
+                        }
+                    })
+                    .collect();
+                ports.sort_by(|a, b| compare(&a.terminal_identifier, &b.terminal_identifier));
+
+                SerializedPart {
+                    name: part.name.clone(),
+                    ports,
+                    datasheet_url: part.datasheet_url.clone(),
+                    metadata: part.metadata.clone().into_iter().collect(),
+                }
+            })
+            .collect();
+        parts.sort_by(|a, b| compare(&a.name, &b.name));
+
+        let mut components: Vec<SerializedComponent> = schematic
+            .components_iter()
+            .map(|component| {
+                let component = component.as_deref();
+                let part = component.part.as_deref().name.clone();
+                SerializedComponent {
+                    name: component.name.clone(),
+                    part,
+                    metadata: component.metadata.clone().into_iter().collect(),
+                }
+            })
+            .collect();
+        components.sort_by(|a, b| compare(&a.name, &b.name));
+
+        let mut nets: Vec<SerializedNet> = schematic
+            .nets_iter()
+            .map(|net| {
+                let net = net.as_deref();
+                let mut connections: Vec<Connection> = net
+                    .connections
+                    .iter()
+                    .map(|(c, p)| Connection::new(c, p))
+                    .collect();
+                connections.sort_by(|a, b| {
+                    compare(&a.component, &b.component)
+                        .then_with(|| compare(&a.terminal_identifier, &b.terminal_identifier))
+                });
+
+                SerializedNet {
+                    name: net.name.clone(),
+                    net_type: net.net_type.clone(),
+                    power_symbol: net.power_symbol.clone(),
+                    connections,
+                }
+            })
+            .collect();
+        nets.sort_by(|a, b| compare(&a.name, &b.name));
+
+        SerializedSchematic {
+            version: FORMAT_VERSION,
+            parts,
+            components,
+            nets,
+        }
+    }
+}
+
+impl TryFrom<&SerializedSchematic> for Schematic {
+    type Error = SchematicError;
+
+    fn try_from(serialized: &SerializedSchematic) -> Result<Self, Self::Error> {
+        if serialized.version != FORMAT_VERSION {
+            return Err(SchematicError::SerializationError(format!(
+                "Unsupported format version {} (expected {})",
+                serialized.version, FORMAT_VERSION
+            )));
+        }
+
+        let mut schematic = Schematic::new();
+
+        for part in serialized.parts.iter() {
+            let mut pb = PartBuilder::default();
+            pb.name(part.name.clone());
+            pb.datasheet_url(part.datasheet_url.clone());
+            for port in part.ports.iter() {
+                pb.port_with_type(&port.terminal_identifier, &port.signal, port.pin_type);
+            }
+            for (key, value) in part.metadata.iter() {
+                pb.metadata(key, value);
+            }
+            schematic.add_part(pb.build()?)?;
+        }
+
+        for component in serialized.components.iter() {
+            let part = schematic
+                .get_part(&component.part)
+                .ok_or(SchematicError::NameNotFound(component.part.clone()))?;
+
+            let mut cb = ComponentBuilder::default();
+            cb.name(component.name.clone());
+            cb.part(part);
+            for (key, value) in component.metadata.iter() {
+                cb.metadata(key, value);
+            }
+            schematic.add_component(cb.build()?)?;
+        }
+
+        for net in serialized.nets.iter() {
+            schematic.add_net(
+                NetBuilder::default()
+                    .name(net.name.clone())
+                    .net_type(net.net_type.clone())
+                    .power_symbol(net.power_symbol.clone())
+                    .build()?,
+            )?;
+            for connection in net.connections.iter() {
+                schematic.connect(
+                    &net.name,
+                    &connection.component,
+                    &connection.terminal_identifier,
+                )?;
+            }
+        }
+
+        Ok(schematic)
+    }
+}
+
+impl Schematic {
+    /// Serialize the schematic as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, SchematicError> {
+        serde_json::to_string_pretty(&SerializedSchematic::from(self))
+            .map_err(|e| SchematicError::SerializationError(e.to_string()))
+    }
+
+    /// Load a schematic previously saved with `to_json`.
+    pub fn from_json(json: &str) -> Result<Schematic, SchematicError> {
+        let serialized: SerializedSchematic = serde_json::from_str(json)
+            .map_err(|e| SchematicError::SerializationError(e.to_string()))?;
+        Schematic::try_from(&serialized)
+    }
+
+    /// Serialize the schematic as CBOR, a more compact binary alternative to
+    /// JSON.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Result<Vec<u8>, SchematicError> {
+        let mut bytes = vec![];
+        ciborium::into_writer(&SerializedSchematic::from(self), &mut bytes)
+            .map_err(|e| SchematicError::SerializationError(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Load a schematic previously saved with `to_cbor`.
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Schematic, SchematicError> {
+        let serialized: SerializedSchematic = ciborium::from_reader(bytes)
+            .map_err(|e| SchematicError::SerializationError(e.to_string()))?;
+        Schematic::try_from(&serialized)
+    }
+}
@@ -1,49 +1,287 @@
-use crate::{Schematic, SchematicError};
+use crate::{
+    part::{PartBuilder, PartRef, PinType},
+    Schematic, SchematicError,
+};
 
-#[allow(dead_code)]
-mod resistor {
-    use crate::{part::PartBuilder, Schematic, SchematicError};
+/// Register a part whose ports are all passive, as they are on KiCad's
+/// generic symbols. `ports` maps terminal identifiers to signals.
+fn register_part(
+    schematic: &mut Schematic,
+    name: &str,
+    ports: &[(&str, &str)],
+) -> Result<PartRef, SchematicError> {
+    let mut pb = PartBuilder::default();
+    pb.name(name.to_string());
+    for (terminal_identifier, signal) in ports {
+        pb.port_with_type(terminal_identifier, signal, PinType::Passive);
+    }
+    schematic.add_part(pb.build()?)
+}
+
+pub mod resistor {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "Resistor";
     pub const RESISTANCE_KEY: &str = "resistance";
 
     pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
-        let part = PartBuilder::default()
-            .name("Resistor".to_string())
-            .port("1".into(), "p1".into())
-            .port("2".into(), "p2".into())
-            .build()?;
-
-        schematic.add_part(part)?;
+        super::register_part(schematic, NAME, &[("1", "p1"), ("2", "p2")])?;
         Ok(())
     }
 }
 
-#[allow(dead_code)]
-mod capacitor {
-    use crate::{part::PartBuilder, Schematic, SchematicError};
+pub mod capacitor {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "Capacitor";
     pub const CAPACITANCE_KEY: &str = "capacitance";
 
     pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
-        let part = PartBuilder::default()
-            .name("Capacitor".to_string())
-            .port("1".into(), "p1".into())
-            .port("2".into(), "p2".into())
-            .build()?;
+        super::register_part(schematic, NAME, &[("1", "p1"), ("2", "p2")])?;
+        Ok(())
+    }
+}
 
-        schematic.add_part(part)?;
+pub mod inductor {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "Inductor";
+    pub const INDUCTANCE_KEY: &str = "inductance";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NAME, &[("1", "p1"), ("2", "p2")])?;
         Ok(())
     }
 }
 
+pub mod ferrite_bead {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "FerriteBead";
+    /// The impedance at the frequency the bead is rated at, e.g. `600R`.
+    pub const IMPEDANCE_KEY: &str = "impedance";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NAME, &[("1", "p1"), ("2", "p2")])?;
+        Ok(())
+    }
+}
+
+pub mod diode {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "Diode";
+    /// The kind of diode, for the symbols which say, e.g. `schottky`.
+    pub const DIODE_TYPE_KEY: &str = "diode_type";
+    pub const SCHOTTKY: &str = "schottky";
+    pub const ZENER: &str = "zener";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        // Numbered like KiCad's diode symbols.
+        super::register_part(schematic, NAME, &[("1", "cathode"), ("2", "anode")])?;
+        Ok(())
+    }
+}
+
+pub mod led {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "LED";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        // Numbered like KiCad's LED symbol.
+        super::register_part(schematic, NAME, &[("1", "cathode"), ("2", "anode")])?;
+        Ok(())
+    }
+}
+
+pub mod bjt {
+    use crate::{Schematic, SchematicError};
+
+    pub const NPN_NAME: &str = "NPN";
+    pub const PNP_NAME: &str = "PNP";
+
+    /// Numbered base, emitter, collector, like SOT-23 transistors and
+    /// KiCad's `Q_NPN_BEC` symbol.
+    const PORTS: &[(&str, &str)] = &[("1", "base"), ("2", "emitter"), ("3", "collector")];
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NPN_NAME, PORTS)?;
+        super::register_part(schematic, PNP_NAME, PORTS)?;
+        Ok(())
+    }
+}
+
+pub mod mosfet {
+    use crate::{Schematic, SchematicError};
+
+    pub const NMOS_NAME: &str = "NMOS";
+    pub const PMOS_NAME: &str = "PMOS";
+
+    /// Numbered gate, source, drain, like SOT-23 MOSFETs and KiCad's
+    /// `Q_NMOS_GSD` symbol.
+    const PORTS: &[(&str, &str)] = &[("1", "gate"), ("2", "source"), ("3", "drain")];
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NMOS_NAME, PORTS)?;
+        super::register_part(schematic, PMOS_NAME, PORTS)?;
+        Ok(())
+    }
+}
+
+pub mod crystal {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "Crystal";
+    pub const FREQUENCY_KEY: &str = "frequency";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NAME, &[("1", "p1"), ("2", "p2")])?;
+        Ok(())
+    }
+}
+
+pub mod test_point {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "TestPoint";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NAME, &[("1", "p1")])?;
+        Ok(())
+    }
+}
+
+pub mod mounting_hole {
+    use crate::{Schematic, SchematicError};
+
+    pub const NAME: &str = "MountingHole";
+
+    pub fn register(schematic: &mut Schematic) -> Result<(), SchematicError> {
+        super::register_part(schematic, NAME, &[])?;
+        Ok(())
+    }
+}
+
+/// Connectors come in any number of pins, so rather than being registered
+/// up front, each size is registered the first time it's used.
+pub mod connector {
+    use crate::{part::PartRef, Schematic, SchematicError};
+
+    const NAME_PREFIX: &str = "Connector_";
+
+    /// The name of a connector with `rows` rows of `pins_per_row` pins, e.g.
+    /// `Connector_01x04`.
+    pub fn name(rows: usize, pins_per_row: usize) -> String {
+        format!("{}{:02}x{:02}", NAME_PREFIX, rows, pins_per_row)
+    }
+
+    /// Whether `name` is the name of a connector from the standard library.
+    pub fn is_connector(name: &str) -> bool {
+        name.strip_prefix(NAME_PREFIX)
+            .and_then(|size| size.split_once('x'))
+            .is_some_and(|(rows, pins)| {
+                [rows, pins]
+                    .iter()
+                    .all(|n| n.len() >= 2 && n.chars().all(|c| c.is_ascii_digit()))
+            })
+    }
+
+    /// Returns the connector with the given size, registering it if needed.
+    /// Its pins are numbered from 1, with signals `p1`, `p2`, etc.
+    pub fn get_or_register(
+        schematic: &mut Schematic,
+        rows: usize,
+        pins_per_row: usize,
+    ) -> Result<PartRef, SchematicError> {
+        let name = name(rows, pins_per_row);
+        if let Some(part) = schematic.get_part(&name) {
+            return Ok(part);
+        }
+
+        let ports: Vec<(String, String)> = (1..=rows * pins_per_row)
+            .map(|i| (i.to_string(), format!("p{}", i)))
+            .collect();
+        let ports: Vec<(&str, &str)> = ports
+            .iter()
+            .map(|(t, s)| (t.as_str(), s.as_str()))
+            .collect();
+        super::register_part(schematic, &name, &ports)
+    }
+}
+
+/// The names of the parts in the standard library, other than connectors.
+pub const PART_NAMES: &[&str] = &[
+    resistor::NAME,
+    capacitor::NAME,
+    inductor::NAME,
+    ferrite_bead::NAME,
+    diode::NAME,
+    led::NAME,
+    bjt::NPN_NAME,
+    bjt::PNP_NAME,
+    mosfet::NMOS_NAME,
+    mosfet::PMOS_NAME,
+    crystal::NAME,
+    test_point::NAME,
+    mounting_hole::NAME,
+];
+
+/// Whether `name` is the name of a part from the standard library.
+pub fn is_standard_part(name: &str) -> bool {
+    PART_NAMES.contains(&name) || connector::is_connector(name)
+}
+
+/// The metadata key a standard library part's value is stored under, e.g.
+/// `resistance` for a resistor.
+pub fn value_key(part_name: &str) -> Option<&'static str> {
+    match part_name {
+        resistor::NAME => Some(resistor::RESISTANCE_KEY),
+        capacitor::NAME => Some(capacitor::CAPACITANCE_KEY),
+        inductor::NAME => Some(inductor::INDUCTANCE_KEY),
+        ferrite_bead::NAME => Some(ferrite_bead::IMPEDANCE_KEY),
+        crystal::NAME => Some(crystal::FREQUENCY_KEY),
+        _ => None,
+    }
+}
+
 impl Schematic {
     pub fn register_standard_library(&mut self) -> Result<(), SchematicError> {
         resistor::register(self)?;
         capacitor::register(self)?;
+        inductor::register(self)?;
+        ferrite_bead::register(self)?;
+        diode::register(self)?;
+        led::register(self)?;
+        bjt::register(self)?;
+        mosfet::register(self)?;
+        crystal::register(self)?;
+        test_point::register(self)?;
+        mounting_hole::register(self)?;
         Ok(())
     }
+
+    /// Remove the standard library part called `name`, if no component uses
+    /// it yet, to make way for a part of the same name defined by a design.
+    /// Returns whether the part was removed.
+    pub fn unregister_standard_part(&mut self, name: &str) -> bool {
+        let in_use = self
+            .components_iter()
+            .any(|c| c.as_deref().part.as_deref().name == name);
+        is_standard_part(name) && !in_use && self.remove_part(name).is_some()
+    }
 }
 
 #[test]
 fn test_stl() {
     let mut schematic = Schematic::new();
     schematic.register_standard_library().unwrap();
+    assert!(schematic.unregister_standard_part(led::NAME));
+    assert!(schematic.get_part(led::NAME).is_none());
+
+    let connector = connector::get_or_register(&mut schematic, 1, 4).unwrap();
+    assert_eq!(connector.as_deref().name, "Connector_01x04");
+    assert_eq!(connector.as_deref().ports_by_terminal_identifier.len(), 4);
+    assert!(connector::get_or_register(&mut schematic, 1, 4).is_ok());
+    assert!(schematic.unregister_standard_part("Connector_01x04"));
 }
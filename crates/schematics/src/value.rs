@@ -0,0 +1,292 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    standard_library::{capacitor, crystal, ferrite_bead, inductor, resistor},
+    SchematicError,
+};
+
+/// SI prefixes, with the exponent they stand for. `K` is accepted for kilo
+/// since KiCad values often use it, e.g. `10K`.
+const PREFIXES: &[(char, i32)] = &[
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('u', -6),
+    ('µ', -6),
+    ('μ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('K', 3),
+    ('M', 6),
+    ('G', 9),
+];
+
+/// The prefixes used when formatting, in Atopile's spelling.
+const FORMAT_PREFIXES: &[(&str, i32)] = &[
+    ("f", -15),
+    ("p", -12),
+    ("n", -9),
+    ("u", -6),
+    ("m", -3),
+    ("", 0),
+    ("k", 3),
+    ("M", 6),
+    ("G", 9),
+];
+
+/// The unit of a physical quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    Ohm,
+    Farad,
+    Henry,
+    Volt,
+    Hertz,
+}
+
+impl Unit {
+    /// The symbol Atopile uses for the unit, e.g. `10kohm`.
+    pub fn atopile_symbol(&self) -> &'static str {
+        match self {
+            Unit::Ohm => "ohm",
+            Unit::Farad => "F",
+            Unit::Henry => "H",
+            Unit::Volt => "V",
+            Unit::Hertz => "Hz",
+        }
+    }
+
+    /// The ways the unit is written after a value, longest first.
+    fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Unit::Ohm => &["ohms", "Ohms", "ohm", "Ohm", "OHM", "Ω", "R"],
+            Unit::Farad => &["F"],
+            Unit::Henry => &["H"],
+            Unit::Volt => &["V"],
+            Unit::Hertz => &["Hz", "hz"],
+        }
+    }
+}
+
+/// The unit of the quantity stored under a metadata key of a standard
+/// library part, e.g. ohms for `resistance`.
+pub fn unit_for_key(key: &str) -> Option<Unit> {
+    match key {
+        resistor::RESISTANCE_KEY => Some(Unit::Ohm),
+        capacitor::CAPACITANCE_KEY => Some(Unit::Farad),
+        inductor::INDUCTANCE_KEY => Some(Unit::Henry),
+        ferrite_bead::IMPEDANCE_KEY => Some(Unit::Ohm),
+        crystal::FREQUENCY_KEY => Some(Unit::Hertz),
+        _ => None,
+    }
+}
+
+/// A physical quantity, e.g. 4.7kΩ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    /// The value in the base unit, without any prefix.
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// Parse a quantity such as `10k`, `4.7kΩ`, `100nF`, or in RKM notation
+    /// `4k7` and `4R7`. The unit may be left out.
+    pub fn parse(text: &str, unit: Unit) -> Result<Quantity, SchematicError> {
+        let invalid = || SchematicError::InvalidValue(text.to_string());
+
+        let mut number = text.trim();
+        if let Some(suffix) = unit.suffixes().iter().find(|s| number.ends_with(*s)) {
+            number = &number[..number.len() - suffix.len()];
+        }
+
+        // At most one prefix is allowed. At the end of the number it's a
+        // multiplier (`4.7k`), anywhere else it also stands in for the
+        // decimal point (`4k7`, `R47`).
+        let markers: Vec<(usize, char)> = number
+            .char_indices()
+            .filter(|(_, c)| !c.is_ascii_digit() && *c != '.')
+            .collect();
+        let (digits, exponent) = match markers.as_slice() {
+            [] => (number.to_string(), 0),
+            [(i, marker)] => {
+                let exponent = match (*marker, unit) {
+                    ('R', Unit::Ohm) => 0,
+                    _ => PREFIXES
+                        .iter()
+                        .find(|(p, _)| p == marker)
+                        .map(|(_, e)| *e)
+                        .ok_or_else(invalid)?,
+                };
+                let (whole, fraction) = (&number[..*i], &number[i + marker.len_utf8()..]);
+                if fraction.is_empty() {
+                    (whole.to_string(), exponent)
+                } else if !whole.contains('.') && !fraction.contains('.') {
+                    (format!("{}.{}", whole, fraction), exponent)
+                } else {
+                    return Err(invalid());
+                }
+            }
+            _ => return Err(invalid()),
+        };
+
+        let digits = match digits.starts_with('.') {
+            true => format!("0{}", digits),
+            false => digits,
+        };
+        let value: f64 = digits.parse().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+
+        Ok(Quantity {
+            value: value * 10f64.powi(exponent),
+            unit,
+        })
+    }
+}
+
+/// Format a value with the largest of `prefixes` it's at least one of, e.g.
+/// `4.7k` or `100n`, falling back to the smallest prefix for tinier values.
+/// The prefixes are given with their exponents in ascending order, and have
+/// to include the empty one for zero.
+pub fn format_with_prefixes(value: f64, prefixes: &[(&str, i32)]) -> String {
+    let magnitude = value.abs();
+    let (prefix, exponent) = match magnitude > 0.0 {
+        true => prefixes
+            .iter()
+            .rev()
+            .find(|(_, e)| magnitude >= 10f64.powi(*e))
+            .unwrap_or(&prefixes[0]),
+        false => &("", 0),
+    };
+
+    // Round away floating point noise, e.g. 4.7 * 1e3.
+    let mantissa = (value / 10f64.powi(*exponent) * 1e6).round() / 1e6;
+    format!("{}{}", mantissa, prefix)
+}
+
+/// Formats the quantity in Atopile's unit syntax, e.g. `4.7kohm`.
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            format_with_prefixes(self.value, FORMAT_PREFIXES),
+            self.unit.atopile_symbol()
+        )
+    }
+}
+
+/// A component's value, e.g. `100nF 50V X7R` or `10k 1%`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentValue {
+    pub quantity: Quantity,
+
+    /// The tolerance in percent.
+    pub tolerance: Option<f64>,
+
+    /// The voltage rating, e.g. of a capacitor.
+    pub voltage: Option<Quantity>,
+
+    /// Anything else in the value, e.g. the dielectric `X7R`.
+    pub extra: Vec<String>,
+}
+
+impl ComponentValue {
+    /// Parse a value whose main quantity is in `unit`. The parts of the value
+    /// may be separated by spaces, commas or slashes.
+    pub fn parse(text: &str, unit: Unit) -> Result<ComponentValue, SchematicError> {
+        let normalized = text.replace("+/-", "±");
+        let tokens: Vec<&str> = normalized
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut quantity = None;
+        let mut tolerance = None;
+        let mut voltage = None;
+        let mut extra = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i].trim_start_matches('±');
+            i += 1;
+            if token.is_empty() {
+                continue;
+            }
+
+            // Ranges such as Atopile's `1kohm to 2kohm` aren't supported.
+            if token == "to" {
+                return Err(SchematicError::InvalidValue(text.to_string()));
+            }
+
+            if let Some(percent) = token.strip_suffix('%') {
+                let percent: f64 = percent
+                    .parse()
+                    .map_err(|_| SchematicError::InvalidValue(text.to_string()))?;
+                tolerance = Some(percent);
+                continue;
+            }
+
+            if quantity.is_none() {
+                // Allow a space between the number and the unit, e.g. `4.7 µH`.
+                if let Some(next) = tokens
+                    .get(i)
+                    .filter(|t| !t.starts_with(|c: char| c.is_ascii_digit()))
+                {
+                    if token.parse::<f64>().is_ok() {
+                        if let Ok(q) = Quantity::parse(&format!("{}{}", token, next), unit) {
+                            quantity = Some(q);
+                            i += 1;
+                            continue;
+                        }
+                    }
+                }
+                if let Ok(q) = Quantity::parse(token, unit) {
+                    quantity = Some(q);
+                    continue;
+                }
+            }
+
+            if unit != Unit::Volt && voltage.is_none() && token.ends_with('V') {
+                if let Ok(q) = Quantity::parse(token, Unit::Volt) {
+                    voltage = Some(q);
+                    continue;
+                }
+            }
+
+            extra.push(token.to_string());
+        }
+
+        Ok(ComponentValue {
+            quantity: quantity.ok_or_else(|| SchematicError::InvalidValue(text.to_string()))?,
+            tolerance,
+            voltage,
+            extra,
+        })
+    }
+}
+
+/// Formats the quantity and tolerance in Atopile's syntax, e.g.
+/// `10kohm +/- 1%`. The voltage rating and anything else are left out.
+impl fmt::Display for ComponentValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.quantity)?;
+        if let Some(tolerance) = self.tolerance {
+            write!(f, " +/- {}%", tolerance)?;
+        }
+        Ok(())
+    }
+}
+
+/// The canonical form of a value stored under `key`, e.g. `4.7kohm` for a
+/// `resistance` of `4k7`. Values which can't be parsed are kept as they are.
+pub fn normalize(key: &str, value: &str) -> String {
+    unit_for_key(key)
+        .and_then(|unit| ComponentValue::parse(value, unit).ok())
+        .map(|v| v.to_string())
+        .unwrap_or(value.to_string())
+}
@@ -0,0 +1,87 @@
+use schematics::{
+    bom::collapse_designators,
+    component::ComponentBuilder,
+    part::{PartBuilder, PartRef},
+    Schematic,
+};
+
+fn add_component(schematic: &mut Schematic, name: &str, part: &PartRef, value: &str) {
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name(name.to_string())
+                .part(part.clone())
+                .metadata("Value", value)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_bom() {
+    let mut schematic = Schematic::new();
+    let resistor = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("R".to_string())
+                .port("1", "~")
+                .port("2", "~")
+                .metadata("Footprint", "R_0603")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let regulator = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("AP2112K".to_string())
+                .port("1", "VIN")
+                .metadata("MPN", "AP2112K-3.3TRG1")
+                .metadata("Manufacturer", "Diodes Inc")
+                .metadata("LCSC", "C51118")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for name in ["R1", "R2", "R3", "R4", "R7", "R10"] {
+        add_component(&mut schematic, name, &resistor, "10k");
+    }
+    add_component(&mut schematic, "R5", &resistor, "4k7");
+    add_component(&mut schematic, "U1", &regulator, "AP2112K-3.3");
+
+    let bom = schematic.bom();
+    assert_eq!(bom.lines.len(), 3);
+    assert_eq!(bom.lines[0].quantity, 6);
+    assert_eq!(bom.lines[0].designator_ranges(), "R1-R4, R7, R10");
+    assert_eq!(bom.lines[1].designators, vec!["R5"]);
+    assert_eq!(bom.lines[2].mpn.as_deref(), Some("AP2112K-3.3TRG1"));
+
+    let csv = bom.to_csv().unwrap();
+    assert!(csv.contains("\"R1-R4, R7, R10\",6,R,10k,,,R_0603,\n"));
+    let jlcpcb = bom.to_jlcpcb_csv().unwrap();
+    assert!(jlcpcb.contains("10k,\"R1,R2,R3,R4,R7,R10\",R_0603,\n"));
+    assert!(jlcpcb.contains("AP2112K-3.3,U1,,C51118\n"));
+    // Only the regulator has an MPN to order.
+    let mouser = bom.to_mouser_csv().unwrap();
+    assert_eq!(mouser.lines().count(), 2);
+    assert!(mouser.contains("AP2112K-3.3TRG1,Diodes Inc,1,U1,AP2112K AP2112K-3.3\n"));
+}
+
+#[test]
+fn test_collapse_designators() {
+    let designators = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(collapse_designators(&designators(&["C1", "C2"])), "C1, C2");
+    assert_eq!(
+        collapse_designators(&designators(&["C1", "C2", "C3", "D1", "D2", "D3"])),
+        "C1-C3, D1-D3"
+    );
+    assert_eq!(
+        collapse_designators(&designators(&["R0", "R1", "R2", "R3"])),
+        "R0-R3"
+    );
+    assert_eq!(
+        collapse_designators(&designators(&["J01", "J02", "J03", "TP"])),
+        "J01, J02, J03, TP"
+    );
+}
@@ -0,0 +1,165 @@
+use schematics::{
+    component::ComponentBuilder,
+    diff::{ComponentChange, Connection, DiffOptions, NetChange, PartChange},
+    net::NetBuilder,
+    part::PartBuilder,
+    Schematic, SchematicError,
+};
+
+fn build_schematic(r1_value: &str, r1_net: &str, led_signal: &str, led_net: &str) -> Schematic {
+    let mut schematic = Schematic::new();
+
+    let resistor = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("R".to_string())
+                .port("1", "~")
+                .port("2", "~")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let led = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("LED".to_string())
+                .port("1", "K")
+                .port("2", led_signal)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .metadata("Value", r1_value)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("D1".to_string())
+                .part(led)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    for net in ["VCC", "GND", led_net] {
+        schematic
+            .add_net(NetBuilder::default().name(net.to_string()).build().unwrap())
+            .unwrap();
+    }
+
+    schematic.connect(r1_net, "R1", "1").unwrap();
+    schematic.connect(led_net, "R1", "2").unwrap();
+    schematic.connect(led_net, "D1", "2").unwrap();
+    schematic.connect("GND", "D1", "1").unwrap();
+    schematic
+}
+
+#[test]
+fn test_identical_schematics() {
+    let left = build_schematic("1k", "VCC", "A", "LED_A");
+    let right = build_schematic("1k", "VCC", "A", "LED_A");
+    assert!(left.diff(&right).is_empty());
+}
+
+#[test]
+fn test_diff() {
+    let left = build_schematic("1k", "VCC", "A", "LED_A");
+    let right = build_schematic("2k2", "GND", "ANODE", "LED_A");
+    let diff = left.diff(&right);
+
+    assert_eq!(
+        diff.parts,
+        vec![PartChange::PinRemapped {
+            part: "LED".to_string(),
+            terminal_identifier: "2".to_string(),
+            old_signal: "A".to_string(),
+            new_signal: "ANODE".to_string(),
+        }]
+    );
+
+    assert_eq!(
+        diff.components,
+        vec![
+            ComponentChange::MetadataChanged {
+                component: "R1".to_string(),
+                key: "Value".to_string(),
+                old: Some("1k".to_string()),
+                new: Some("2k2".to_string()),
+            },
+            ComponentChange::MovedNet {
+                component: "R1".to_string(),
+                terminal_identifier: "1".to_string(),
+                old_net: Some("VCC".to_string()),
+                new_net: Some("GND".to_string()),
+            },
+        ]
+    );
+
+    let r1_1 = Connection {
+        component: "R1".to_string(),
+        terminal_identifier: "1".to_string(),
+    };
+    assert_eq!(
+        diff.nets,
+        vec![
+            NetChange::Connected {
+                net: "GND".to_string(),
+                connection: r1_1.clone(),
+            },
+            NetChange::Disconnected {
+                net: "VCC".to_string(),
+                connection: r1_1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_renamed_net() {
+    let left = build_schematic("1k", "VCC", "A", "Net-(D1-A)");
+    let right = build_schematic("1k", "VCC", "A", "LED_A");
+
+    assert_eq!(
+        left.diff(&right).nets,
+        vec![NetChange::Renamed {
+            old_net: "Net-(D1-A)".to_string(),
+            new_net: "LED_A".to_string(),
+            similarity: 1.0,
+        }]
+    );
+
+    let options = DiffOptions {
+        net_rename_threshold: None,
+    };
+    let diff = left.diff_with_options(&right, &options).unwrap();
+    assert!(matches!(diff.nets[0], NetChange::Added { .. }));
+    assert!(matches!(diff.nets[1], NetChange::Removed { .. }));
+    assert_eq!(diff.components.len(), 2);
+}
+
+#[test]
+fn test_invalid_rename_threshold() {
+    let schematic = build_schematic("1k", "VCC", "A", "LED_A");
+    for threshold in [-0.1, 1.5, f64::NAN] {
+        let options = DiffOptions {
+            net_rename_threshold: Some(threshold),
+        };
+        assert!(
+            matches!(
+                schematic.diff_with_options(&schematic, &options),
+                Err(SchematicError::InvalidRenameThreshold(_))
+            ),
+            "threshold {}",
+            threshold
+        );
+    }
+}
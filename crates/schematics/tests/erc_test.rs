@@ -0,0 +1,182 @@
+use schematics::{
+    component::ComponentBuilder,
+    erc::{ErcConfig, Rule, Severity},
+    net::NetBuilder,
+    part::{PartBuilder, PinType},
+    Schematic,
+};
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+
+    let led = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("LED".to_string())
+                .port("1", "K")
+                .port("2", "A")
+                .metadata("Footprint", "LED_0603")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let resistor = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("R".to_string())
+                .port("1", "~")
+                .port("2", "~")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("D1".to_string())
+                .part(led)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    for name in ["LED_A", "GND"] {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+    schematic.connect("LED_A", "D1", "2").unwrap();
+    schematic.connect("LED_A", "R1", "2").unwrap();
+    schematic.connect("GND", "D1", "1").unwrap();
+    schematic
+}
+
+#[test]
+fn test_erc() {
+    let report = build_schematic().erc(&ErcConfig::default());
+    let found: Vec<(Rule, Severity, Option<String>, Option<String>)> = report
+        .diagnostics
+        .iter()
+        .map(|d| (d.rule, d.severity, d.net.clone(), d.component.clone()))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            (
+                Rule::SinglePinNet,
+                Severity::Warning,
+                Some("GND".to_string()),
+                None
+            ),
+            (
+                Rule::UnconnectedPort,
+                Severity::Warning,
+                None,
+                Some("D1".to_string())
+            ),
+            (
+                Rule::UnconnectedPort,
+                Severity::Warning,
+                None,
+                Some("R1".to_string())
+            ),
+            (
+                Rule::MissingFootprint,
+                Severity::Error,
+                None,
+                Some("R1".to_string())
+            ),
+        ]
+    );
+    assert!(report.has_errors());
+}
+
+#[test]
+fn test_erc_config() {
+    let config: ErcConfig = serde_json::from_str(
+        r#"{"rules": {"missing_footprint": "off", "single_pin_net": "error"}}"#,
+    )
+    .unwrap();
+    let report = build_schematic().erc(&config);
+
+    assert!(report
+        .diagnostics
+        .iter()
+        .all(|d| d.rule != Rule::MissingFootprint));
+    assert_eq!(report.count(Severity::Error), 1);
+    assert_eq!(report.count(Severity::Warning), 2);
+}
+
+#[test]
+fn test_erc_pin_types() {
+    let mut schematic = Schematic::new();
+    let regulator = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("LDO".to_string())
+                .port_with_type("1", "VIN", PinType::PowerIn)
+                .port_with_type("2", "VOUT", PinType::PowerOut)
+                .port_with_type("3", "NC", PinType::NoConnect)
+                .metadata("Footprint", "SOT-23")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for name in ["U1", "U2"] {
+        schematic
+            .add_component(
+                ComponentBuilder::default()
+                    .name(name.to_string())
+                    .part(regulator.clone())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+    for name in ["VIN", "VOUT"] {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+    schematic.connect("VIN", "U1", "1").unwrap();
+    schematic.connect("VIN", "U2", "1").unwrap();
+    schematic.connect("VOUT", "U1", "2").unwrap();
+    schematic.connect("VOUT", "U2", "2").unwrap();
+
+    let report = schematic.erc(&ErcConfig::default());
+    let found: Vec<(Rule, Option<String>)> = report
+        .diagnostics
+        .iter()
+        .map(|d| (d.rule, d.net.clone()))
+        .collect();
+
+    // The no-connect pins aren't reported as unconnected.
+    assert_eq!(
+        found,
+        vec![
+            (Rule::PowerNetWithoutDriver, Some("VIN".to_string())),
+            (Rule::ShortedOutputs, Some("VOUT".to_string())),
+        ]
+    );
+}
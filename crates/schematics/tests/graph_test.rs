@@ -0,0 +1,109 @@
+use schematics::{
+    component::{ComponentBuilder, KICAD_SHEET_PATH_KEY},
+    graph::GraphFilter,
+    net::{NetBuilder, NetType},
+    Schematic, SchematicError,
+};
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    for (name, part, sheet) in [
+        ("R1", "Resistor", "/"),
+        ("C1", "Capacitor", "/Power/"),
+        ("D1", "LED", "/"),
+    ] {
+        let part = schematic.get_part(part).unwrap();
+        schematic
+            .add_component(
+                ComponentBuilder::default()
+                    .name(name.to_string())
+                    .part(part)
+                    .metadata(KICAD_SHEET_PATH_KEY, sheet)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    for (name, net_type, pins) in [
+        ("VCC", NetType::Power, &[("R1", "1"), ("C1", "1")][..]),
+        ("GND", NetType::Ground, &[("C1", "2"), ("D1", "1")]),
+        ("LED \"A\"", NetType::Unknown, &[("R1", "2"), ("D1", "2")]),
+    ] {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .net_type(net_type)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        for (component, pin) in pins {
+            schematic.connect(name, component, pin).unwrap();
+        }
+    }
+    schematic
+}
+
+#[test]
+fn test_to_dot() {
+    let schematic = build_schematic();
+    let dot = schematic.to_dot(&GraphFilter::default()).unwrap();
+    assert!(dot.starts_with("graph schematic {\n"));
+    assert!(dot.contains("  \"component:C1\" [label=\"C1\\nCapacitor\", shape=box];\n"));
+    assert!(dot.contains("fillcolor=\"#f4a6a6\""));
+    assert!(dot.contains("  \"component:R1\" -- \"net:LED \\\"A\\\"\" [label=\"2\"];\n"));
+    assert_eq!(dot.matches(" -- ").count(), 6);
+
+    // Just the power sheet, and the nets leaving it.
+    let filter = GraphFilter {
+        sheet: Some(vec!["Power".to_string()]),
+        ..Default::default()
+    };
+    let dot = schematic.to_dot(&filter).unwrap();
+    assert!(!dot.contains("\"component:R1\""));
+    assert!(dot.contains("\"net:VCC\" ["));
+    assert!(!dot.contains("\"net:LED"));
+    assert_eq!(dot.matches(" -- ").count(), 2);
+
+    // The components on GND, and their other nets.
+    let filter = GraphFilter {
+        net: Some("GND".to_string()),
+        ..Default::default()
+    };
+    let dot = schematic.to_dot(&filter).unwrap();
+    assert!(!dot.contains("\"component:R1\""));
+    assert!(dot.contains("\"net:VCC\" ["));
+    assert_eq!(dot.matches(" -- ").count(), 4);
+}
+
+#[test]
+fn test_to_dot_net_in_sheet() {
+    let schematic = build_schematic();
+
+    // The components on GND that are on the power sheet.
+    let filter = GraphFilter {
+        sheet: Some(vec!["Power".to_string()]),
+        net: Some("GND".to_string()),
+    };
+    let dot = schematic.to_dot(&filter).unwrap();
+    assert!(dot.contains("\"component:C1\" ["));
+    assert!(!dot.contains("\"component:D1\""));
+    assert!(dot.contains("\"net:VCC\" ["));
+    assert!(!dot.contains("\"net:LED"));
+    assert_eq!(dot.matches(" -- ").count(), 2);
+}
+
+#[test]
+fn test_to_dot_unknown_net() {
+    let filter = GraphFilter {
+        net: Some("VBAT".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        build_schematic().to_dot(&filter),
+        Err(SchematicError::NameNotFound(name)) if name == "VBAT"
+    ));
+}
@@ -0,0 +1,106 @@
+use schematics::{
+    component::ComponentBuilder,
+    net::{NetBuilder, NetType},
+    net_inference::{NetTypeOverrides, NetTypeSource},
+    part::{PartBuilder, PinType},
+    Schematic,
+};
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+    let mcu = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("MCU".to_string())
+                .port_with_type("1", "VDD", PinType::PowerIn)
+                .port_with_type("2", "GND", PinType::PowerIn)
+                .port_with_type("3", "PA0", PinType::Bidirectional)
+                .port_with_type("4", "PA1", PinType::Bidirectional)
+                .port_with_type("5", "PA2", PinType::Passive)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    for name in ["U1", "U2"] {
+        schematic
+            .add_component(
+                ComponentBuilder::default()
+                    .name(name.to_string())
+                    .part(mcu.clone())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    let nets = [
+        ("+3V3", None, vec![("U1", "1")]),
+        ("Net-(U1-GND)", None, vec![("U1", "2"), ("U2", "2")]),
+        ("Net-(U1-PA0)", None, vec![("U1", "3"), ("U2", "3")]),
+        ("/sensors/I2C_SCL", None, vec![("U1", "4"), ("U2", "5")]),
+        ("AGND", None, vec![("U1", "5")]),
+        ("VMOTOR", Some("power:VMOTOR"), vec![("U2", "1")]),
+        ("/LED_K", None, vec![("U2", "4")]),
+    ];
+    for (name, power_symbol, connections) in nets {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .power_symbol(power_symbol.map(|s| s.to_string()))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        for (component, pin) in connections {
+            schematic.connect(name, component, pin).unwrap();
+        }
+    }
+    schematic
+}
+
+#[test]
+fn test_infer_net_types() {
+    let mut schematic = build_schematic();
+    let report = schematic
+        .infer_net_types(&NetTypeOverrides::default())
+        .unwrap();
+
+    let found: Vec<(&str, NetType, NetTypeSource)> = report
+        .nets
+        .iter()
+        .map(|d| (d.net.as_str(), d.net_type.clone(), d.source))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("+3V3", NetType::Power, NetTypeSource::PinTypes),
+            ("/LED_K", NetType::Digital, NetTypeSource::PinTypes),
+            ("/sensors/I2C_SCL", NetType::Digital, NetTypeSource::Name),
+            ("AGND", NetType::Ground, NetTypeSource::Name),
+            ("Net-(U1-GND)", NetType::Ground, NetTypeSource::PinTypes),
+            ("Net-(U1-PA0)", NetType::Digital, NetTypeSource::PinTypes),
+            ("VMOTOR", NetType::Power, NetTypeSource::PowerSymbol),
+        ]
+    );
+    assert_eq!(
+        schematic.get_net("AGND").unwrap().as_deref().net_type,
+        NetType::Ground
+    );
+}
+
+#[test]
+fn test_net_type_overrides() {
+    let mut schematic = build_schematic();
+    let overrides: NetTypeOverrides =
+        serde_json::from_str(r#"{"nets": {"AGND": "analog"}}"#).unwrap();
+    schematic.infer_net_types(&overrides).unwrap();
+    assert_eq!(
+        schematic.get_net("AGND").unwrap().as_deref().net_type,
+        NetType::Analog
+    );
+
+    let overrides: NetTypeOverrides =
+        serde_json::from_str(r#"{"nets": {"MISSING": "power"}}"#).unwrap();
+    assert!(schematic.infer_net_types(&overrides).is_err());
+}
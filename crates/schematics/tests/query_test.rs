@@ -0,0 +1,158 @@
+use schematics::{
+    component::{ComponentBuilder, KICAD_SHEET_PATH_KEY},
+    net::NetBuilder,
+    part::PartBuilder,
+    query::{Query, QueryError},
+    Schematic,
+};
+
+/// Add components from a table of names, parts and sheet paths, and nets
+/// from a table of names and the pins they connect.
+fn add_tables(
+    schematic: &mut Schematic,
+    components: &[(&str, &str, &str)],
+    nets: &[(&str, &[(&str, &str)])],
+) {
+    for (name, part, sheet) in components {
+        let part = schematic.get_part(part).unwrap();
+        schematic
+            .add_component(
+                ComponentBuilder::default()
+                    .name(name.to_string())
+                    .part(part)
+                    .metadata(KICAD_SHEET_PATH_KEY, sheet)
+                    .metadata("Footprint", "R_0603")
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    for (name, pins) in nets {
+        schematic
+            .add_net(
+                NetBuilder::default()
+                    .name(name.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        for (component, pin) in pins.iter() {
+            schematic.connect(name, component, pin).unwrap();
+        }
+    }
+}
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    schematic
+        .add_part(
+            PartBuilder::default()
+                .name("STM32G031".to_string())
+                .port("1", "SDA")
+                .port("2", "SCL")
+                .port("3", "PA0")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    add_tables(
+        &mut schematic,
+        &[
+            ("U1", "STM32G031", "/MCU/"),
+            ("R1", "Resistor", "/MCU/"),
+            ("R2", "Resistor", "/"),
+        ],
+        &[
+            ("SDA", &[("U1", "1"), ("R1", "2")]),
+            ("SCL", &[("U1", "2"), ("R2", "2")]),
+            ("+3V3", &[("R1", "1"), ("R2", "1")]),
+        ],
+    );
+    schematic
+}
+
+fn query(schematic: &Schematic, text: &str) -> Vec<String> {
+    let result = schematic.query(&Query::parse(text).unwrap()).unwrap();
+    result.rows.iter().map(|row| row[0].clone()).collect()
+}
+
+#[test]
+fn test_query() {
+    let schematic = build_schematic();
+    assert_eq!(query(&schematic, "nets"), vec!["+3V3", "SCL", "SDA"]);
+    assert_eq!(
+        query(&schematic, "nets where connects(U1.SDA)"),
+        vec!["SDA"]
+    );
+    assert_eq!(query(&schematic, "nets where connects(U1.2)"), vec!["SCL"]);
+    assert_eq!(
+        query(&schematic, "nets where connects(U1) and not name ~ \"SC?\""),
+        vec!["SDA"]
+    );
+    assert_eq!(
+        query(
+            &schematic,
+            "components where part ~ \"STM32*\" and sheet = \"MCU\""
+        ),
+        vec!["U1"]
+    );
+    assert_eq!(
+        query(
+            &schematic,
+            "COMPONENTS WHERE (connects(SCL) or connects(SDA)) and footprint = R_0603 and name != U1"
+        ),
+        vec!["R1", "R2"]
+    );
+    let result = schematic
+        .query(&Query::parse("pins of U1 unconnected").unwrap())
+        .unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0][1], "3");
+
+    let result = schematic
+        .query(&Query::parse("pins of U1 where connects(R1.2) or signal = PA0").unwrap())
+        .unwrap();
+    assert_eq!(
+        result.columns,
+        vec!["component", "pin", "signal", "type", "net"]
+    );
+    assert_eq!(
+        result.rows,
+        vec![
+            vec!["U1", "1", "SDA", "unspecified", "SDA"],
+            vec!["U1", "3", "PA0", "unspecified", ""],
+        ]
+    );
+    assert_eq!(result.records()[0]["net"], "SDA");
+}
+
+#[test]
+fn test_query_errors() {
+    let schematic = build_schematic();
+    assert_eq!(
+        Query::parse("nets where foo = 1"),
+        Err(QueryError::UnknownField {
+            field: "foo".to_string(),
+            target: "nets".to_string()
+        })
+    );
+    assert!(matches!(
+        Query::parse("nets where (name = SDA"),
+        Err(QueryError::Syntax { position: 22, .. })
+    ));
+    assert!(matches!(
+        Query::parse("parts"),
+        Err(QueryError::Syntax { position: 0, .. })
+    ));
+    assert!(matches!(
+        Query::parse("nets where name = \"SDA"),
+        Err(QueryError::Syntax { position: 18, .. })
+    ));
+    assert_eq!(
+        schematic.query(&Query::parse("pins of U9").unwrap()),
+        Err(QueryError::NameNotFound("U9".to_string()))
+    );
+}
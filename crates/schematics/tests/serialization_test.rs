@@ -0,0 +1,103 @@
+use schematics::{
+    component::ComponentBuilder,
+    net::{NetBuilder, NetType},
+    part::PartBuilder,
+    serialization::SerializedSchematic,
+    Schematic,
+};
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+
+    let led = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("LTST-C191KRKT".to_string())
+                .port("1", "K")
+                .port("2", "A")
+                .datasheet_url(Some("https://example.com/led.pdf".to_string()))
+                .metadata("Footprint", "LED_0603")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let resistor = schematic.get_part("Resistor").unwrap();
+
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("D1".to_string())
+                .part(led)
+                .metadata("Sheetname", "Status")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    schematic
+        .add_net(
+            NetBuilder::default()
+                .name("GND".to_string())
+                .net_type(NetType::Ground)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic
+        .add_net(
+            NetBuilder::default()
+                .name("LED_A".to_string())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic.connect("GND", "D1", "1").unwrap();
+    schematic.connect("LED_A", "D1", "2").unwrap();
+    schematic.connect("LED_A", "R1", "2").unwrap();
+    schematic
+}
+
+#[test]
+fn test_json_round_trip() {
+    let schematic = build_schematic();
+    let json = schematic.to_json().unwrap();
+    let loaded = Schematic::from_json(&json).unwrap();
+
+    assert_eq!(
+        SerializedSchematic::from(&schematic),
+        SerializedSchematic::from(&loaded)
+    );
+    assert!(schematic.diff(&loaded).is_empty());
+    assert_eq!(loaded.to_json().unwrap(), json);
+}
+
+#[test]
+fn test_unsupported_version() {
+    let json =
+        build_schematic()
+            .to_json()
+            .unwrap()
+            .replacen("\"version\": 1", "\"version\": 99", 1);
+    assert!(Schematic::from_json(&json).is_err());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_round_trip() {
+    let schematic = build_schematic();
+    let loaded = Schematic::from_cbor(&schematic.to_cbor().unwrap()).unwrap();
+    assert_eq!(
+        SerializedSchematic::from(&schematic),
+        SerializedSchematic::from(&loaded)
+    );
+}
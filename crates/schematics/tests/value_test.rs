@@ -0,0 +1,88 @@
+use schematics::value::{format_with_prefixes, normalize, ComponentValue, Quantity, Unit};
+
+#[test]
+fn test_parse_quantity() {
+    let cases = [
+        ("10k", Unit::Ohm, "10kohm"),
+        ("10K", Unit::Ohm, "10kohm"),
+        ("4k7", Unit::Ohm, "4.7kohm"),
+        ("4R7", Unit::Ohm, "4.7ohm"),
+        ("R47", Unit::Ohm, "470mohm"),
+        ("100R", Unit::Ohm, "100ohm"),
+        ("2.2MΩ", Unit::Ohm, "2.2Mohm"),
+        ("0", Unit::Ohm, "0ohm"),
+        ("100nF", Unit::Farad, "100nF"),
+        ("100fF", Unit::Farad, "100fF"),
+        ("0.5pF", Unit::Farad, "500fF"),
+        ("0.0005pF", Unit::Farad, "0.5fF"),
+        ("4u7", Unit::Farad, "4.7uF"),
+        ("0.1µF", Unit::Farad, "100nF"),
+        (".47uF", Unit::Farad, "470nF"),
+        ("10uH", Unit::Henry, "10uH"),
+        ("3.3V", Unit::Volt, "3.3V"),
+        ("32.768kHz", Unit::Hertz, "32.768kHz"),
+        ("16MHz", Unit::Hertz, "16MHz"),
+    ];
+    for (text, unit, expected) in cases {
+        let quantity = Quantity::parse(text, unit).unwrap();
+        assert_eq!(quantity.to_string(), expected, "parsing {}", text);
+    }
+
+    for text in ["", "DNP", "4k7k", "4.7k7", "-10k", "10kF"] {
+        assert!(
+            Quantity::parse(text, Unit::Ohm).is_err(),
+            "parsing {}",
+            text
+        );
+    }
+}
+
+#[test]
+fn test_parse_component_value() {
+    let value = ComponentValue::parse("100nF 50V X7R", Unit::Farad).unwrap();
+    assert_eq!(
+        value.quantity,
+        Quantity::parse("100n", Unit::Farad).unwrap()
+    );
+    assert_eq!(
+        value.voltage,
+        Some(Quantity::parse("50", Unit::Volt).unwrap())
+    );
+    assert_eq!(value.extra, vec!["X7R".to_string()]);
+    assert_eq!(value.to_string(), "100nF");
+
+    let value = ComponentValue::parse("10k 1%", Unit::Ohm).unwrap();
+    assert_eq!(value.tolerance, Some(1.0));
+    assert_eq!(value.to_string(), "10kohm +/- 1%");
+
+    let value = ComponentValue::parse("4.7 µH ±20%", Unit::Henry).unwrap();
+    assert_eq!(value.to_string(), "4.7uH +/- 20%");
+
+    let value = ComponentValue::parse("10uF/16V", Unit::Farad).unwrap();
+    assert_eq!(value.to_string(), "10uF");
+    assert_eq!(value.voltage.unwrap().to_string(), "16V");
+
+    let value = ComponentValue::parse("16 MHz 20ppm", Unit::Hertz).unwrap();
+    assert_eq!(value.to_string(), "16MHz");
+    assert_eq!(value.extra, vec!["20ppm".to_string()]);
+
+    assert!(ComponentValue::parse("1kohm to 2kohm", Unit::Ohm).is_err());
+}
+
+#[test]
+fn test_format_with_prefixes() {
+    let prefixes = [("m", -3), ("", 0), ("k", 3), ("Meg", 6)];
+    assert_eq!(format_with_prefixes(2.2e6, &prefixes), "2.2Meg");
+    assert_eq!(format_with_prefixes(4.7e9, &prefixes), "4700Meg");
+    assert_eq!(format_with_prefixes(-4700.0, &prefixes), "-4.7k");
+    assert_eq!(format_with_prefixes(1e-6, &prefixes), "0.001m");
+    assert_eq!(format_with_prefixes(0.0, &prefixes), "0");
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("resistance", "4k7"), "4.7kohm");
+    assert_eq!(normalize("resistance", "10kohm +/- 5%"), "10kohm +/- 5%");
+    assert_eq!(normalize("capacitance", "DNP"), "DNP");
+    assert_eq!(normalize("Value", "4k7"), "4k7");
+}
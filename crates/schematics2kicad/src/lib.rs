@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+
+use natord::compare;
+use schematics::{
+    component::{ComponentRef, KICAD_SHEET_PATH_KEY, KICAD_SHEET_TSTAMPS_KEY, KICAD_TSTAMP_KEY},
+    part::{PartRef, PinType},
+    Schematic,
+};
+
+/// The name KiCad uses for a pin type.
+fn kicad_pin_type(pin_type: PinType) -> &'static str {
+    match pin_type {
+        PinType::Tristate => "tri_state",
+        _ => pin_type.as_str(),
+    }
+}
+
+/// A list in the netlist, rendered as `(head inline... children...)`. Children
+/// are written on their own lines, unless the node is flat.
+struct Node {
+    head: &'static str,
+    inline: Vec<String>,
+    children: Vec<Node>,
+    flat: bool,
+}
+
+impl Node {
+    fn new(head: &'static str) -> Self {
+        Node {
+            head,
+            inline: vec![],
+            children: vec![],
+            flat: false,
+        }
+    }
+
+    /// A list holding a single string, e.g. `(value "10k")`.
+    fn leaf(head: &'static str, value: &str) -> Self {
+        Node::new(head).with_inline(&quote(value))
+    }
+
+    /// A list of leaves written on a single line, e.g. `(pin (num "1") (name "A"))`.
+    fn flat(head: &'static str, leaves: &[(&'static str, &str)]) -> Self {
+        let mut node = Node::new(head);
+        node.flat = true;
+        for (head, value) in leaves {
+            node = node.with_child(Node::leaf(head, value));
+        }
+        node
+    }
+
+    fn with_inline(mut self, item: &str) -> Self {
+        self.inline.push(item.to_string());
+        self
+    }
+
+    fn with_child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        out.push('(');
+        out.push_str(self.head);
+        for item in self.inline.iter() {
+            out.push(' ');
+            out.push_str(item);
+        }
+        for child in self.children.iter() {
+            if self.flat {
+                out.push(' ');
+            } else {
+                out.push('\n');
+                out.push_str(&"  ".repeat(depth + 1));
+            }
+            child.write(out, depth + 1);
+        }
+        out.push(')');
+    }
+}
+
+/// Quote a string the way KiCad does.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Render a list holding a single string, for use on the same line as its
+/// parent's head.
+fn quote_leaf(head: &str, value: &str) -> String {
+    format!("({} {})", head, quote(value))
+}
+
+fn sorted_metadata<'a>(
+    metadata: impl Iterator<Item = (&'a String, &'a String)>,
+) -> Vec<(&'a String, &'a String)> {
+    let mut metadata: Vec<_> = metadata.collect();
+    metadata.sort_by(|a, b| compare(a.0, b.0));
+    metadata
+}
+
+fn component_node(component: &ComponentRef) -> Node {
+    let component = component.as_deref();
+    let part = component.part.as_deref();
+
+    // KiCad requires a value, so fall back to the value of the part, or its
+    // name.
+    let value = component
+        .metadata
+        .get("Value")
+        .or(part.metadata.get("Value"))
+        .cloned()
+        .unwrap_or(part.name.clone());
+
+    let mut node = Node::new("comp")
+        .with_inline(&quote_leaf("ref", &component.name))
+        .with_child(Node::leaf("value", &value));
+
+    if let Some(footprint) = component
+        .metadata
+        .get("Footprint")
+        .or(part.metadata.get("Footprint"))
+    {
+        node = node.with_child(Node::leaf("footprint", footprint));
+    }
+
+    if let Some(datasheet) = component
+        .metadata
+        .get("Datasheet")
+        .or(part.datasheet_url.as_ref())
+    {
+        node = node.with_child(Node::leaf("datasheet", datasheet));
+    }
+
+    node = node.with_child(Node::flat(
+        "libsource",
+        &[("lib", ""), ("part", &part.name), ("description", "")],
+    ));
+
+    // The value, footprint, datasheet, sheet path and UUIDs have fields of
+    // their own.
+    let properties = component.metadata.iter().filter(|(key, _)| {
+        ![
+            "Value",
+            "Footprint",
+            "Datasheet",
+            KICAD_SHEET_PATH_KEY,
+            KICAD_SHEET_TSTAMPS_KEY,
+            KICAD_TSTAMP_KEY,
+        ]
+        .contains(&key.as_str())
+    });
+    for (key, value) in sorted_metadata(properties) {
+        node = node.with_child(Node::flat("property", &[("name", key), ("value", value)]));
+    }
+
+    if let Some(names) = component.metadata.get(KICAD_SHEET_PATH_KEY) {
+        let tstamps = component
+            .metadata
+            .get(KICAD_SHEET_TSTAMPS_KEY)
+            .map(|t| t.as_str())
+            .unwrap_or("/");
+        node = node.with_child(Node::flat(
+            "sheetpath",
+            &[("names", names), ("tstamps", tstamps)],
+        ));
+    }
+
+    if let Some(tstamp) = component.metadata.get(KICAD_TSTAMP_KEY) {
+        node = node.with_child(Node::leaf("tstamps", tstamp));
+    }
+
+    node
+}
+
+fn libpart_node(part: &PartRef) -> Node {
+    let part = part.as_deref();
+
+    let mut node = Node::new("libpart")
+        .with_inline(&quote_leaf("lib", ""))
+        .with_inline(&quote_leaf("part", &part.name));
+
+    if let Some(datasheet) = part.datasheet_url.as_ref() {
+        node = node.with_child(Node::leaf("docs", datasheet));
+    }
+
+    if !part.metadata.is_empty() {
+        let mut fields = Node::new("fields");
+        for (key, value) in sorted_metadata(part.metadata.iter()) {
+            fields = fields.with_child(
+                Node::new("field")
+                    .with_inline(&quote_leaf("name", key))
+                    .with_inline(&quote(value)),
+            );
+        }
+        node = node.with_child(fields);
+    }
+
+    let mut ports: Vec<_> = part.ports_by_terminal_identifier.values().collect();
+    ports.sort_by(|a, b| {
+        compare(
+            &a.as_deref().terminal_identifier,
+            &b.as_deref().terminal_identifier,
+        )
+    });
+
+    let mut pins = Node::new("pins");
+    for port in ports {
+        let port = port.as_deref();
+        pins = pins.with_child(Node::flat(
+            "pin",
+            &[
+                ("num", &port.terminal_identifier),
+                ("name", &port.signal),
+                ("type", kicad_pin_type(port.pin_type)),
+            ],
+        ));
+    }
+
+    node.with_child(pins)
+}
+
+/// Export a Schematic as a KiCad netlist (`.net`), in the `(export (version
+/// "E") ...)` format read by pcbnew. Only parts used by a component are
+/// written to the library parts.
+pub fn kicad_netlist_from_schematics(schematic: &Schematic) -> String {
+    let mut components: Vec<&ComponentRef> = schematic.components_iter().collect();
+    components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+    let mut components_node = Node::new("components");
+    for component in components.iter() {
+        components_node = components_node.with_child(component_node(component));
+    }
+
+    let used_parts: BTreeSet<String> = components
+        .iter()
+        .map(|c| c.as_deref().part.as_deref().name.clone())
+        .collect();
+    let mut parts: Vec<&PartRef> = schematic
+        .parts_iter()
+        .filter(|p| used_parts.contains(&p.as_deref().name))
+        .collect();
+    parts.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+    let mut libparts_node = Node::new("libparts");
+    for part in parts {
+        libparts_node = libparts_node.with_child(libpart_node(part));
+    }
+
+    let mut nets: Vec<_> = schematic.nets_iter().collect();
+    nets.sort_by(|a, b| compare(&a.name(), &b.name()));
+
+    let mut nets_node = Node::new("nets");
+    for (code, net) in nets.iter().enumerate() {
+        let net = net.as_deref();
+        let mut net_node = Node::new("net")
+            .with_inline(&quote_leaf("code", &(code + 1).to_string()))
+            .with_inline(&quote_leaf("name", &net.name));
+
+        let mut connections: Vec<_> = net.connections.iter().collect();
+        connections.sort_by(|a, b| {
+            compare(&a.0.as_deref().name, &b.0.as_deref().name).then_with(|| {
+                compare(
+                    &a.1.as_deref().terminal_identifier,
+                    &b.1.as_deref().terminal_identifier,
+                )
+            })
+        });
+
+        for (component, port) in connections {
+            let port = port.as_deref();
+            let component = component.as_deref();
+            let mut leaves = vec![
+                ("ref", component.name.as_str()),
+                ("pin", port.terminal_identifier.as_str()),
+            ];
+            if !port.signal.is_empty() && port.signal != "~" {
+                leaves.push(("pinfunction", port.signal.as_str()));
+            }
+            leaves.push(("pintype", kicad_pin_type(port.pin_type)));
+            net_node = net_node.with_child(Node::flat("node", &leaves));
+        }
+
+        nets_node = nets_node.with_child(net_node);
+    }
+
+    let export = Node::new("export")
+        .with_inline(&quote_leaf("version", "E"))
+        .with_child(Node::new("design").with_child(Node::leaf("tool", "diode")))
+        .with_child(components_node)
+        .with_child(libparts_node)
+        .with_child(nets_node);
+
+    let mut out = String::new();
+    export.write(&mut out, 0);
+    out.push('\n');
+    out
+}
@@ -0,0 +1,110 @@
+use insta::assert_snapshot;
+use schematics::{
+    component::ComponentBuilder,
+    net::NetBuilder,
+    part::{PartBuilder, PinType},
+    Schematic,
+};
+use schematics2kicad::kicad_netlist_from_schematics;
+
+#[test]
+fn test_export() {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    // Use KiCad's own LED symbol rather than the generic one.
+    schematic.unregister_standard_part("LED");
+
+    let resistor = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("R".to_string())
+                .port_with_type("1", "~", PinType::Passive)
+                // As read from a KiCad symbol.
+                .port_with_type("2", "~", "free".parse().unwrap())
+                .metadata("Reference", "R")
+                .metadata("Value", "R")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let led = schematic
+        .add_part(
+            PartBuilder::default()
+                .name("LED".to_string())
+                .port_with_type("1", "K", PinType::Passive)
+                .port_with_type("2", "A", PinType::Passive)
+                .datasheet_url(Some("~".to_string()))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("R1".to_string())
+                .part(resistor)
+                .metadata("Value", "1k")
+                .metadata("Footprint", "Resistor_SMD:R_0603_1608Metric")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    schematic
+        .add_component(
+            ComponentBuilder::default()
+                .name("D1".to_string())
+                .part(led)
+                .metadata("Sheetname", "Status \"LED\"")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    for net in ["VCC", "GND", "Net-(D1-A)"] {
+        schematic
+            .add_net(NetBuilder::default().name(net.to_string()).build().unwrap())
+            .unwrap();
+    }
+    schematic.connect("VCC", "R1", "1").unwrap();
+    schematic.connect("Net-(D1-A)", "R1", "2").unwrap();
+    schematic.connect("Net-(D1-A)", "D1", "2").unwrap();
+    schematic.connect("GND", "D1", "1").unwrap();
+
+    assert_snapshot!(kicad_netlist_from_schematics(&schematic), @r###"
+    (export (version "E")
+      (design
+        (tool "diode"))
+      (components
+        (comp (ref "D1")
+          (value "LED")
+          (datasheet "~")
+          (libsource (lib "") (part "LED") (description ""))
+          (property (name "Sheetname") (value "Status \"LED\"")))
+        (comp (ref "R1")
+          (value "1k")
+          (footprint "Resistor_SMD:R_0603_1608Metric")
+          (libsource (lib "") (part "R") (description ""))))
+      (libparts
+        (libpart (lib "") (part "LED")
+          (docs "~")
+          (pins
+            (pin (num "1") (name "K") (type "passive"))
+            (pin (num "2") (name "A") (type "passive"))))
+        (libpart (lib "") (part "R")
+          (fields
+            (field (name "Reference") "R")
+            (field (name "Value") "R"))
+          (pins
+            (pin (num "1") (name "~") (type "passive"))
+            (pin (num "2") (name "~") (type "free")))))
+      (nets
+        (net (code "1") (name "GND")
+          (node (ref "D1") (pin "1") (pinfunction "K") (pintype "passive")))
+        (net (code "2") (name "Net-(D1-A)")
+          (node (ref "D1") (pin "2") (pinfunction "A") (pintype "passive"))
+          (node (ref "R1") (pin "2") (pintype "free")))
+        (net (code "3") (name "VCC")
+          (node (ref "R1") (pin "1") (pintype "passive")))))
+    "###);
+}
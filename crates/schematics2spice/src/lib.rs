@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+};
+
+use natord::compare;
+use schematics::{
+    component::{Component, ComponentRef},
+    part::{Part, PortRef, MPN_KEY},
+    standard_library::{capacitor, inductor, resistor},
+    value::{format_with_prefixes, ComponentValue, Unit},
+    Schematic,
+};
+
+/// SPICE's scale factors. `M` is milli, so mega is `Meg`.
+const SCALE_FACTORS: &[(&str, i32)] = &[
+    ("f", -15),
+    ("p", -12),
+    ("n", -9),
+    ("u", -6),
+    ("m", -3),
+    ("", 0),
+    ("k", 3),
+    ("Meg", 6),
+    ("G", 9),
+    ("T", 12),
+];
+
+/// The metadata keys a component's MPN is read from, in order of preference.
+const MPN_KEYS: &[&str] = &["MPN", MPN_KEY];
+
+/// Nets that are written as SPICE's ground node, `0`.
+const GROUND_NETS: &[&str] = &["0", "GND", "GNDA", "AGND"];
+
+/// A `.subckt` from a model file, which components are instantiated from in
+/// place of their part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpiceModel {
+    /// The file to `.include`.
+    pub path: PathBuf,
+    /// The name of the subcircuit.
+    pub subckt: String,
+    /// The number of pins of the subcircuit, which are connected to the
+    /// component's ports in natural order of their terminal identifiers.
+    pub pins: usize,
+}
+
+impl SpiceModel {
+    /// Read the first `.subckt` defined in `contents`, the contents of the
+    /// model file at `path`. Returns `None` if there is none.
+    pub fn parse(path: PathBuf, contents: &str) -> Option<SpiceModel> {
+        // Continuation lines start with `+`.
+        let mut lines: Vec<String> = vec![];
+        for line in contents.lines() {
+            match line.trim_start().strip_prefix('+') {
+                Some(rest) if !lines.is_empty() => {
+                    let last = lines.last_mut().unwrap();
+                    last.push(' ');
+                    last.push_str(rest);
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        lines.iter().find_map(|line| {
+            let mut words = line.split_whitespace();
+            if !words.next()?.eq_ignore_ascii_case(".subckt") {
+                return None;
+            }
+            let subckt = words.next()?.to_string();
+            // Parameters follow the pins, e.g. `params: R=1k` or `R=1k`.
+            let pins = words
+                .take_while(|w| !w.contains('=') && !w.eq_ignore_ascii_case("params:"))
+                .count();
+            Some(SpiceModel {
+                path: path.clone(),
+                subckt,
+                pins,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpiceOptions {
+    /// The title of the deck, written on its first line.
+    pub title: String,
+
+    /// Only export the components placed on this sheet and the sheets below
+    /// it, e.g. `["Power", "LDO"]`. Nets leaving the sheet become nodes of
+    /// the deck.
+    pub sheet: Option<Vec<String>>,
+
+    /// Models to instantiate components from, keyed by MPN.
+    pub models: HashMap<String, SpiceModel>,
+}
+
+/// Make a name usable as a SPICE node or subcircuit name, which can't hold
+/// whitespace, or characters with a meaning of their own.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c,
+            '_' | '-' | '+' | '.' | '/' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// The first letter of a SPICE element name says what it is, so prefix the
+/// name of the component with it unless it already starts with it, e.g. `R1`
+/// and `RFB1`, but `XU1`.
+fn element_name(letter: char, component: &Component) -> String {
+    let name = sanitize(&component.name);
+    // Subcircuit instances always get the prefix, since `X1` reads as a
+    // crystal's designator.
+    if letter != 'X' && name.to_ascii_uppercase().starts_with(letter) {
+        name
+    } else {
+        format!("{}{}", letter, name)
+    }
+}
+
+fn sorted_ports(part: &Part) -> Vec<PortRef> {
+    let mut ports: Vec<PortRef> = part
+        .ports_by_terminal_identifier
+        .values()
+        .cloned()
+        .collect();
+    ports.sort_by(|a, b| {
+        compare(
+            &a.as_deref().terminal_identifier,
+            &b.as_deref().terminal_identifier,
+        )
+    });
+    ports
+}
+
+/// Assigns each net a node name, unique once sanitized.
+struct Nodes {
+    by_net: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl Nodes {
+    fn new() -> Self {
+        Nodes {
+            by_net: HashMap::new(),
+            taken: HashSet::from(["0".to_string()]),
+        }
+    }
+
+    fn node(&mut self, net: &str) -> String {
+        if GROUND_NETS.iter().any(|g| g.eq_ignore_ascii_case(net)) {
+            return "0".to_string();
+        }
+        if let Some(node) = self.by_net.get(net) {
+            return node.clone();
+        }
+
+        let base = sanitize(net);
+        let mut node = base.clone();
+        let mut i = 1;
+        while self.taken.contains(&node) {
+            i += 1;
+            node = format!("{}_{}", base, i);
+        }
+        self.taken.insert(node.clone());
+        self.by_net.insert(net.to_string(), node.clone());
+        node
+    }
+}
+
+/// The placeholder for a part without a model: an empty subcircuit with a pin
+/// per port, named after the port's signal.
+fn placeholder_subckt(part: &Part) -> String {
+    let ports = sorted_ports(part);
+    let signals: Vec<String> = ports
+        .iter()
+        .map(|p| sanitize(&p.as_deref().signal))
+        .collect();
+    let pins: Vec<String> = ports
+        .iter()
+        .zip(signals.iter())
+        .map(|(port, signal)| {
+            let unique = signals.iter().filter(|s| *s == signal).count() == 1;
+            match unique && !signal.is_empty() && signal != "_" {
+                true => signal.clone(),
+                false => format!("p{}", sanitize(&port.as_deref().terminal_identifier)),
+            }
+        })
+        .collect();
+
+    let name = sanitize(&part.name);
+    format!(
+        ".subckt {} {}\n* No model for {}, replace with one or pass a model keyed by its MPN\n.ends {}\n",
+        name,
+        pins.join(" "),
+        part.name,
+        name
+    )
+}
+
+/// The unit of the value of a part that's written as a SPICE primitive,
+/// with the letter of the primitive and the metadata key the value is read
+/// from.
+fn primitive(part: &Part) -> Option<(char, Unit, &'static str)> {
+    match part.name.as_str() {
+        resistor::NAME => Some(('R', Unit::Ohm, resistor::RESISTANCE_KEY)),
+        capacitor::NAME => Some(('C', Unit::Farad, capacitor::CAPACITANCE_KEY)),
+        inductor::NAME => Some(('L', Unit::Henry, inductor::INDUCTANCE_KEY)),
+        _ => None,
+    }
+}
+
+fn in_sheet(component: &Component, sheet: &Option<Vec<String>>) -> bool {
+    match sheet {
+        Some(sheet) => component.sheet_path().starts_with(sheet),
+        None => true,
+    }
+}
+
+/// Export a Schematic as a SPICE deck (`.cir`). Resistors, capacitors and
+/// inductors from the standard library become R, C and L primitives with
+/// their parsed values. Every other component is instantiated from the model
+/// given for its MPN, or else from an empty placeholder subcircuit for its
+/// part, to be filled in by hand.
+pub fn spice_netlist_from_schematics(schematic: &Schematic, options: &SpiceOptions) -> String {
+    let mut components: Vec<&ComponentRef> = schematic
+        .components_iter()
+        .filter(|c| in_sheet(&c.as_deref(), &options.sheet))
+        .collect();
+    components.sort_by(|a, b| compare(&a.as_deref().name, &b.as_deref().name));
+
+    // The net of each port, by component and terminal identifier.
+    let mut nets: Vec<_> = schematic.nets_iter().collect();
+    nets.sort_by(|a, b| compare(&a.name(), &b.name()));
+    let mut port_nets: HashMap<(String, String), String> = HashMap::new();
+    for net in nets {
+        let net = net.as_deref();
+        for (component, port) in net.connections.iter() {
+            port_nets.insert(
+                (
+                    component.as_deref().name.clone(),
+                    port.as_deref().terminal_identifier.clone(),
+                ),
+                net.name.clone(),
+            );
+        }
+    }
+
+    let mut nodes = Nodes::new();
+    let mut includes: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut placeholders: BTreeMap<String, String> = BTreeMap::new();
+    let mut elements = vec![];
+    for component in components {
+        let component = component.as_deref();
+        let part = component.part.as_deref();
+
+        // Unconnected ports each get a node of their own.
+        let pins: Vec<String> = sorted_ports(&part)
+            .iter()
+            .map(|port| {
+                let terminal = port.as_deref().terminal_identifier.clone();
+                let net = port_nets
+                    .get(&(component.name.clone(), terminal.clone()))
+                    .cloned()
+                    .unwrap_or(format!("NC_{}_{}", component.name, terminal));
+                nodes.node(&net)
+            })
+            .collect();
+
+        if let Some((letter, unit, key)) = primitive(&part) {
+            let text = component
+                .get_metadata("Value")
+                .or(component.get_metadata(key))
+                .unwrap_or_default();
+            match ComponentValue::parse(&text, unit) {
+                Ok(value) => elements.push(format!(
+                    "{} {} {}",
+                    element_name(letter, &component),
+                    pins.join(" "),
+                    format_with_prefixes(value.quantity.value, SCALE_FACTORS)
+                )),
+                Err(_) => elements.push(format!(
+                    "* {} left out, its value {:?} can't be parsed",
+                    component.name, text
+                )),
+            }
+            continue;
+        }
+
+        let model = MPN_KEYS
+            .iter()
+            .filter_map(|key| component.get_metadata(key))
+            .find_map(|mpn| options.models.get(&mpn));
+        let subckt = match model {
+            Some(model) if model.pins != pins.len() => {
+                elements.push(format!(
+                    "* {} left out, it has {} ports, but {} has {} pins",
+                    component.name,
+                    pins.len(),
+                    model.subckt,
+                    model.pins
+                ));
+                continue;
+            }
+            Some(model) => {
+                includes.insert(model.subckt.clone(), model.path.clone());
+                model.subckt.clone()
+            }
+            None => {
+                let name = sanitize(&part.name);
+                placeholders
+                    .entry(name.clone())
+                    .or_insert_with(|| placeholder_subckt(&part));
+                name
+            }
+        };
+        elements.push(format!(
+            "{} {} {}",
+            element_name('X', &component),
+            pins.join(" "),
+            subckt
+        ));
+    }
+
+    let mut out = format!("* {}\n", options.title);
+    if !includes.is_empty() {
+        out.push('\n');
+    }
+    for path in includes.values() {
+        out.push_str(&format!(".include \"{}\"\n", path.display()));
+    }
+    for subckt in placeholders.values() {
+        out.push('\n');
+        out.push_str(subckt);
+    }
+    out.push('\n');
+    for element in elements {
+        out.push_str(&element);
+        out.push('\n');
+    }
+    out.push_str(".end\n");
+    out
+}
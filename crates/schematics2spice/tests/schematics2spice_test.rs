@@ -0,0 +1,145 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use insta::assert_snapshot;
+use schematics::{
+    component::{ComponentBuilder, KICAD_SHEET_PATH_KEY},
+    net::NetBuilder,
+    part::PartBuilder,
+    Schematic,
+};
+use schematics2spice::{spice_netlist_from_schematics, SpiceModel, SpiceOptions};
+
+fn add_component(schematic: &mut Schematic, name: &str, part: &str, metadata: &[(&str, &str)]) {
+    let part = schematic.get_part(part).unwrap();
+    let mut cb = ComponentBuilder::default();
+    cb.name(name.to_string()).part(part);
+    for (key, value) in metadata {
+        cb.metadata(key, value);
+    }
+    schematic.add_component(cb.build().unwrap()).unwrap();
+}
+
+fn build_schematic() -> Schematic {
+    let mut schematic = Schematic::new();
+    schematic.register_standard_library().unwrap();
+    schematic
+        .add_part(
+            PartBuilder::default()
+                .name("AP2112K-3.3".to_string())
+                .port("1", "VIN")
+                .port("2", "GND")
+                .port("3", "EN")
+                .port("5", "VOUT")
+                .metadata("MPN", "AP2112K-3.3TRG1")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+    let power = [(KICAD_SHEET_PATH_KEY, "/Power/")];
+    add_component(&mut schematic, "U1", "AP2112K-3.3", &power);
+    add_component(
+        &mut schematic,
+        "C1",
+        "Capacitor",
+        &[
+            (KICAD_SHEET_PATH_KEY, "/Power/"),
+            ("Value", "100nF 50V X7R"),
+        ],
+    );
+    add_component(&mut schematic, "R1", "Resistor", &[("Value", "4k7")]);
+    add_component(&mut schematic, "R2", "Resistor", &[("Value", "DNP")]);
+    add_component(&mut schematic, "D1", "LED", &[]);
+
+    for (net, pins) in [
+        ("VIN", &[("U1", "1"), ("U1", "3")][..]),
+        ("+3V3", &[("U1", "5"), ("C1", "1"), ("R1", "1")]),
+        ("LED A", &[("R1", "2"), ("D1", "2")]),
+        ("GND", &[("U1", "2"), ("C1", "2"), ("D1", "1")]),
+    ] {
+        schematic
+            .add_net(NetBuilder::default().name(net.to_string()).build().unwrap())
+            .unwrap();
+        for (component, pin) in pins {
+            schematic.connect(net, component, pin).unwrap();
+        }
+    }
+    schematic
+}
+
+#[test]
+fn test_export() {
+    let schematic = build_schematic();
+    let options = SpiceOptions {
+        title: "Blinky".to_string(),
+        ..Default::default()
+    };
+
+    assert_snapshot!(spice_netlist_from_schematics(&schematic, &options), @r###"
+    * Blinky
+
+    .subckt AP2112K-3.3 VIN GND EN VOUT
+    * No model for AP2112K-3.3, replace with one or pass a model keyed by its MPN
+    .ends AP2112K-3.3
+
+    .subckt LED cathode anode
+    * No model for LED, replace with one or pass a model keyed by its MPN
+    .ends LED
+
+    C1 +3V3 0 100n
+    XD1 0 LED_A LED
+    R1 +3V3 LED_A 4.7k
+    * R2 left out, its value "DNP" can't be parsed
+    XU1 VIN 0 VIN +3V3 AP2112K-3.3
+    .end
+    "###);
+}
+
+#[test]
+fn test_export_sheet_with_model() {
+    let schematic = build_schematic();
+    let model = SpiceModel::parse(
+        PathBuf::from("models/AP2112K-3.3TRG1.lib"),
+        "* AP2112K\n.SUBCKT AP2112K VIN GND EN\n+ VOUT params: ILIMIT=0.6\n.ENDS\n",
+    )
+    .unwrap();
+    assert_eq!(model.pins, 4);
+    let options = SpiceOptions {
+        title: "Power".to_string(),
+        sheet: Some(vec!["Power".to_string()]),
+        models: HashMap::from([("AP2112K-3.3TRG1".to_string(), model)]),
+    };
+
+    assert_snapshot!(spice_netlist_from_schematics(&schematic, &options), @r###"
+    * Power
+
+    .include "models/AP2112K-3.3TRG1.lib"
+
+    C1 +3V3 0 100n
+    XU1 VIN 0 VIN +3V3 AP2112K
+    .end
+    "###);
+}
+
+#[test]
+fn test_export_model_pin_mismatch() {
+    let schematic = build_schematic();
+    let model = SpiceModel::parse(
+        PathBuf::from("models/AP2112K-3.3TRG1.lib"),
+        ".subckt AP2112K VIN GND VOUT\n.ends\n",
+    )
+    .unwrap();
+    let options = SpiceOptions {
+        title: "Power".to_string(),
+        sheet: Some(vec!["Power".to_string()]),
+        models: HashMap::from([("AP2112K-3.3TRG1".to_string(), model)]),
+    };
+
+    assert_snapshot!(spice_netlist_from_schematics(&schematic, &options), @r###"
+    * Power
+
+    C1 +3V3 0 100n
+    * U1 left out, it has 4 ports, but AP2112K has 3 pins
+    .end
+    "###);
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use crate::load::load_schematic;
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum BomFormat {
+    /// A line per group of components, with a column per field.
+    Csv,
+    /// The BOM lines as JSON, for scripts.
+    Json,
+    /// The CSV layout of JLCPCB's assembly service.
+    Jlcpcb,
+    /// The CSV layout Mouser's BOM tool imports.
+    Mouser,
+}
+
+#[derive(clap::Args)]
+pub struct BomArgs {
+    #[clap(
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module to generate the BOM of"
+    )]
+    input: PathBuf,
+
+    #[clap(long, value_enum, default_value_t = BomFormat::Csv, help = "Output format")]
+    format: BomFormat,
+
+    #[clap(
+        short,
+        long,
+        help = "File to write the BOM to, instead of standard output"
+    )]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: BomArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let schematic = load_schematic(&args.input)?;
+    let bom = schematic.bom();
+
+    let contents = match args.format {
+        BomFormat::Csv => bom.to_csv()?,
+        BomFormat::Json => serde_json::to_string_pretty(&bom)? + "\n",
+        BomFormat::Jlcpcb => bom.to_jlcpcb_csv()?,
+        BomFormat::Mouser => bom.to_mouser_csv()?,
+    };
+
+    match args.output {
+        Some(output) => std::fs::write(&output, contents)
+            .map_err(|e| format!("Failed to write \"{}\": {}", output.display(), e))?,
+        None => print!("{}", contents),
+    }
+
+    Ok(())
+}
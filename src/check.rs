@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use colored::*;
+use schematics::erc::{ErcConfig, ErcReport, Severity};
+
+use crate::load::load_schematic;
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum CheckFormat {
+    /// Human-readable, colored output for the terminal.
+    Text,
+    /// The diagnostics as JSON, for scripts.
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct CheckArgs {
+    #[clap(
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module to check"
+    )]
+    input: PathBuf,
+
+    #[clap(
+        long,
+        help = "JSON file setting the severity of each rule, e.g. {\"rules\": {\"single_pin_net\": \"off\"}}"
+    )]
+    config: Option<PathBuf>,
+
+    #[clap(long, value_enum, default_value_t = CheckFormat::Text, help = "Output format")]
+    format: CheckFormat,
+}
+
+fn print_text(report: &ErcReport) {
+    for diagnostic in report.diagnostics.iter() {
+        let label = match diagnostic.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+            Severity::Off => continue,
+        };
+        println!(
+            "{}{} {}",
+            label,
+            format!("[{}]:", diagnostic.rule.name()).bold(),
+            diagnostic.message
+        );
+    }
+
+    let errors = report.count(Severity::Error);
+    let warnings = report.count(Severity::Warning);
+    if errors == 0 && warnings == 0 {
+        println!("{}", "No problems found.".green());
+    } else {
+        println!();
+        println!("{} error(s), {} warning(s)", errors, warnings);
+    }
+}
+
+pub fn run(args: CheckArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let schematic = load_schematic(&args.input)?;
+
+    let config = match args.config.as_ref() {
+        Some(path) => {
+            let config = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+            serde_json::from_str(&config)
+                .map_err(|e| format!("Invalid config \"{}\": {}", path.display(), e))?
+        }
+        None => ErcConfig::default(),
+    };
+
+    let report = schematic.erc(&config);
+    match args.format {
+        CheckFormat::Text => print_text(&report),
+        CheckFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    if report.has_errors() {
+        return Err(format!(
+            "Electrical rules check failed with {} error(s)",
+            report.count(Severity::Error)
+        )
+        .into());
+    }
+
+    Ok(())
+}
@@ -1,17 +1,25 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
 
-use atopile::{AtopileNormalizer, AtopileProject};
+use atopile::{AtopileError, AtopileNormalizer, AtopileProject, MergeReport};
 use colored::*;
 use expanduser::expanduser;
 use inquire::{autocompletion::Replacement, Autocomplete, Confirm, CustomUserError, Text};
-use kicad2schematics::schematics_from_kicad_netlist;
+use schematics::SchematicError;
+use similar::TextDiff;
+use thiserror::Error;
+
+use crate::load::{load_schematic, NetTypeArgs};
 
 #[derive(clap::Args)]
 pub struct ConvertArgs {
     #[clap(
         short,
         long,
-        help = "Path to the KiCad netlist file (.net) to be converted"
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch) or project (.kicad_pro) to be converted"
     )]
     netlist: Option<PathBuf>,
 
@@ -28,6 +36,35 @@ pub struct ConvertArgs {
         help = "Force overwrite of existing files in the output directory"
     )]
     force: bool,
+
+    #[clap(
+        short,
+        long,
+        conflicts_with = "force",
+        help = "Merge the conversion into the existing project, keeping edits made to the generated files"
+    )]
+    merge: bool,
+
+    #[clap(
+        long,
+        help = "Show the files that would be created or changed, with a diff of each change, without writing anything"
+    )]
+    dry_run: bool,
+
+    #[clap(
+        long,
+        help = "Create a missing output directory with `ato create` rather than the built-in project template"
+    )]
+    ato_create: bool,
+
+    #[clap(
+        long,
+        help = "Fail instead of prompting for missing arguments or confirmation (the default when standard input isn't a terminal)"
+    )]
+    non_interactive: bool,
+
+    #[clap(flatten)]
+    net_types: NetTypeArgs,
 }
 
 #[derive(Clone, Default)]
@@ -153,13 +190,60 @@ impl Autocomplete for FilePathCompleter {
     }
 }
 
+/// Why a conversion failed. Each has its own exit code, so that scripts can
+/// tell them apart.
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    #[error("Missing argument {0}, which can't be prompted for in non-interactive mode")]
+    MissingInput(&'static str),
+
+    #[error("Directory already exists: \"{0}\". Pass --force to overwrite it, or --merge")]
+    OverwriteRefused(PathBuf),
+
+    #[error("Failed to load the design: {0}")]
+    ParseFailure(String),
+
+    #[error("Failed to convert names to Atopile: {0}")]
+    NormalizationConflict(String),
+
+    #[error("Merge left conflicts in {0} file(s), resolve the lines between <<<<<<< and >>>>>>>")]
+    MergeConflict(usize),
+}
+
+impl ConvertError {
+    pub fn exit_code(&self) -> u8 {
+        // 1 is for any other error, and 2 for invalid arguments.
+        match self {
+            ConvertError::MissingInput(_) => 3,
+            ConvertError::OverwriteRefused(_) => 4,
+            ConvertError::ParseFailure(_) => 5,
+            ConvertError::NormalizationConflict(_) => 6,
+            ConvertError::MergeConflict(_) => 7,
+        }
+    }
+}
+
 impl ConvertArgs {
+    /// Whether missing arguments can be prompted for.
+    fn interactive(&self) -> bool {
+        !self.non_interactive && std::io::stdin().is_terminal()
+    }
+
     pub fn complete(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
         let mut required_input = false;
 
+        if !self.interactive() {
+            if self.netlist.is_none() {
+                return Err(ConvertError::MissingInput("--netlist").into());
+            }
+            if self.output_dir.is_none() {
+                return Err(ConvertError::MissingInput("--output-dir").into());
+            }
+        }
+
         while self.netlist.is_none() {
             required_input = true;
-            let netlist = Text::new("Path to KiCad Netlist (.net): ")
+            let netlist = Text::new("Path to KiCad netlist, schematic or project: ")
                 .with_autocomplete(FilePathCompleter::default())
                 .prompt()?;
             let path: PathBuf = expanduser(netlist)?;
@@ -186,7 +270,10 @@ impl ConvertArgs {
 
         let output_dir = self.output_dir.as_ref().unwrap();
 
-        if !self.force && output_dir.exists() {
+        if !self.force && !self.merge && !self.dry_run && output_dir.exists() {
+            if !self.interactive() {
+                return Err(ConvertError::OverwriteRefused(output_dir.clone()).into());
+            }
             required_input = true;
 
             let overwrite = Confirm::new(&format!(
@@ -199,10 +286,7 @@ impl ConvertArgs {
             if overwrite {
                 self.force = true;
             } else {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::AlreadyExists,
-                    format!("Directory already exists: \"{}\"", output_dir.display()),
-                )));
+                return Err(ConvertError::OverwriteRefused(output_dir.clone()).into());
             }
         }
 
@@ -225,6 +309,24 @@ impl ConvertArgs {
             command.push_str("--force ");
         }
 
+        if self.merge {
+            command.push_str("--merge ");
+        }
+
+        if self.dry_run {
+            command.push_str("--dry-run ");
+        }
+
+        if self.ato_create {
+            command.push_str("--ato-create ");
+        }
+
+        if self.non_interactive {
+            command.push_str("--non-interactive ");
+        }
+
+        command.push_str(&self.net_types.command_args());
+
         command.trim().to_string()
     }
 }
@@ -244,41 +346,187 @@ pub fn run(mut args: ConvertArgs) -> Result<(), Box<dyn std::error::Error>> {
         .to_str()
         .unwrap();
 
-    if !args.output_dir.as_ref().unwrap().exists() {
-        println!("Output does not exist, calling `ato create`...");
+    if args.ato_create && !args.output_dir.as_ref().unwrap().exists() {
+        if args.dry_run {
+            println!("Output does not exist, `ato create` would be called to create it.");
+        } else {
+            ato_create(args.output_dir.as_ref().unwrap(), project_name)?;
+        }
+    }
 
-        let mut command = std::process::Command::new("ato");
-        command
-            .arg("create")
-            .arg(project_name)
-            .current_dir(args.output_dir.as_ref().unwrap().parent().unwrap());
+    // Read the netlist or schematic and parse it into schematics.
+    let mut schematics = load_schematic(args.netlist.as_ref().ok_or("netlist file not found")?)
+        .map_err(|e| ConvertError::ParseFailure(e.to_string()))?;
+    args.net_types.apply(&mut schematics)?;
 
-        let mut child = command.spawn()?;
-        let status = child.wait()?;
+    // Normalize the names in the netlist.
+    let normalizer = AtopileNormalizer::default();
+    schematics.normalize(normalizer).map_err(|e| match e {
+        SchematicError::NormalizationError(e) => {
+            ConvertError::NormalizationConflict(e.to_string()).into()
+        }
+        e => Box::<dyn std::error::Error>::from(e),
+    })?;
 
-        if !status.success() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to create project: {}", status),
-            )));
+    // Generate the source files.
+    let project = AtopileProject::from_schematic(project_name.to_string(), &schematics).map_err(
+        |e| match e {
+            AtopileError::NameCollisionError(name) => {
+                ConvertError::NormalizationConflict(format!("Name conflict: {}", name)).into()
+            }
+            e => Box::<dyn std::error::Error>::from(e),
+        },
+    )?;
+    let output_dir = args.output_dir.as_ref().unwrap();
+    if args.dry_run {
+        // Merging leaves the files that it doesn't write untouched.
+        let (mut writes, untouched, removes) = match args.merge {
+            true => {
+                let plan = project.plan_merge(output_dir)?;
+                let untouched = plan
+                    .generated
+                    .keys()
+                    .filter(|path| !plan.writes.contains_key(*path))
+                    .cloned()
+                    .collect();
+                (plan.writes, untouched, plan.report.removed)
+            }
+            false => (project.generate_files()?, vec![], vec![]),
+        };
+        if !args.ato_create || output_dir.exists() {
+            for (path, contents) in project.scaffold_files() {
+                if !output_dir.join(&path).exists() {
+                    writes.insert(path, contents);
+                }
+            }
         }
+        print_dry_run(output_dir, &writes, &untouched, &removes)?;
+        return Ok(());
+    }
+
+    // Fill in whatever is missing to make the output a valid Atopile project.
+    for path in project.scaffold(output_dir)? {
+        println!("  {} {} (scaffold)", "+".green(), path.display());
+    }
 
-        println!("Created project!");
+    if !args.merge {
+        project.generate_to_directory(output_dir)?;
+        println!("{}", "Conversion completed successfully!".green());
+        return Ok(());
     }
 
-    // Read netlist and parse it into schematics.
-    let netlist = std::fs::read_to_string(args.netlist.ok_or("netlist file not found")?)?;
-    let mut schematics = schematics_from_kicad_netlist(&netlist)?;
+    let report = project.merge_into_directory(output_dir)?;
+    print_merge_report(&report);
+    if report.has_conflicts() {
+        return Err(ConvertError::MergeConflict(report.conflicted.len()).into());
+    }
 
-    // Normalize the names in the netlist.
-    let normalizer = AtopileNormalizer::default();
-    schematics.normalize(normalizer)?;
+    println!("{}", "Merge completed successfully!".green());
 
-    // Generate the source files.
-    let project = AtopileProject::from_schematic(project_name.to_string(), &schematics)?;
-    project.generate_to_directory(&args.output_dir.unwrap())?;
+    Ok(())
+}
 
-    println!("{}", "Conversion completed successfully!".green());
+/// Create an Atopile project called `project_name` at `output_dir` with
+/// `ato create`.
+fn ato_create(output_dir: &Path, project_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Output does not exist, calling `ato create`...");
+
+    let mut command = std::process::Command::new("ato");
+    command
+        .arg("create")
+        .arg(project_name)
+        .current_dir(output_dir.parent().unwrap());
+
+    let mut child = command.spawn()?;
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to create project: {}", status),
+        )));
+    }
 
+    println!("Created project!");
     Ok(())
 }
+
+/// Print how writing `writes` and removing `removes` would change the files in
+/// `output_dir`, with a unified diff of each changed file. `untouched` are
+/// files that would be left as they are.
+fn print_dry_run(
+    output_dir: &Path,
+    writes: &BTreeMap<PathBuf, String>,
+    untouched: &[PathBuf],
+    removes: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut created, mut changed, mut unchanged) = (0, 0, untouched.len());
+    for path in untouched.iter() {
+        println!("  {} {} (unchanged)", "=".normal(), path.display());
+    }
+    for (path, contents) in writes.iter() {
+        let existing = match std::fs::read_to_string(output_dir.join(path)) {
+            Ok(existing) => Some(existing),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        match existing {
+            None => {
+                created += 1;
+                println!("  {} {} (create)", "+".green(), path.display());
+            }
+            Some(existing) if existing == *contents => {
+                unchanged += 1;
+                println!("  {} {} (unchanged)", "=".normal(), path.display());
+            }
+            Some(existing) => {
+                changed += 1;
+                println!("  {} {} (change)", "~".yellow(), path.display());
+                let diff = TextDiff::from_lines(&existing, contents);
+                let name = path.display().to_string();
+                let unified = diff
+                    .unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+                    .to_string();
+                for line in unified.lines() {
+                    let line = match line.chars().next() {
+                        Some('+') => line.green(),
+                        Some('-') => line.red(),
+                        Some('@') => line.cyan(),
+                        _ => line.normal(),
+                    };
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+    for path in removes.iter() {
+        println!("  {} {} (remove)", "-".red(), path.display());
+    }
+
+    println!();
+    println!(
+        "Dry run: {} to create, {} to change, {} to remove, {} unchanged. Nothing was written.",
+        created,
+        changed,
+        removes.len(),
+        unchanged
+    );
+    Ok(())
+}
+
+fn print_merge_report(report: &MergeReport) {
+    let sections = [
+        ("created", &report.created, "+".green()),
+        ("updated", &report.updated, "~".yellow()),
+        ("removed", &report.removed, "-".red()),
+        ("kept, since it was edited", &report.kept, "=".normal()),
+        ("conflicted", &report.conflicted, "!".red().bold()),
+    ];
+    for (label, paths, marker) in sections {
+        for path in paths.iter() {
+            println!("  {} {} ({})", marker, path.display(), label);
+        }
+    }
+}
@@ -1,11 +1,511 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use colored::*;
+use natord::compare;
+use schematics::{
+    diff::{ComponentChange, Connection, DiffOptions, NetChange, PartChange, SchematicDiff},
+    Schematic,
+};
+
+use crate::load::load_schematic;
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Human-readable, colored output for the terminal.
+    Text,
+    /// The structured diff as JSON, for scripts.
+    Json,
+    /// A Markdown summary grouped by sheet, e.g. for a pull request comment.
+    Markdown,
+    /// GitHub Actions workflow commands, which show up as annotations.
+    Github,
+}
 
 #[derive(clap::Args)]
 pub struct DiffArgs {
+    #[clap(
+        help = "Path to the original KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module"
+    )]
     left: PathBuf,
+
+    #[clap(
+        help = "Path to the updated KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module"
+    )]
     right: PathBuf,
+
+    #[clap(
+        long,
+        default_value_t = 0.5,
+        value_parser = parse_fraction,
+        help = "Minimum fraction of shared pins for two differently-named nets to be reported as a rename"
+    )]
+    rename_threshold: f64,
+
+    #[clap(
+        long,
+        help = "Only match nets by name, reporting renamed nets as removed and added"
+    )]
+    no_renames: bool,
+
+    #[clap(long, value_enum, default_value_t = DiffFormat::Text, help = "Output format")]
+    format: DiffFormat,
+}
+
+/// Parse a fraction from 0 to 1, e.g. `0.5`.
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", value))?;
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!("{} is not between 0 and 1", fraction));
+    }
+    Ok(fraction)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A format-agnostic description of a single change.
+struct ChangeLine {
+    kind: ChangeKind,
+    description: String,
+}
+
+impl ChangeLine {
+    fn new(kind: ChangeKind, description: String) -> Self {
+        Self { kind, description }
+    }
+}
+
+fn describe_value(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("\"{}\"", value),
+        None => "(none)".to_string(),
+    }
+}
+
+fn join_connections(connections: &[Connection]) -> String {
+    connections
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_part_change(change: &PartChange) -> ChangeLine {
+    use ChangeKind::*;
+
+    match change {
+        PartChange::Added { part } => ChangeLine::new(Added, part.clone()),
+        PartChange::Removed { part } => ChangeLine::new(Removed, part.clone()),
+        PartChange::PortAdded {
+            part,
+            terminal_identifier,
+            signal,
+        } => ChangeLine::new(
+            Changed,
+            format!("{}: pin {} ({}) added", part, terminal_identifier, signal),
+        ),
+        PartChange::PortRemoved {
+            part,
+            terminal_identifier,
+            signal,
+        } => ChangeLine::new(
+            Changed,
+            format!("{}: pin {} ({}) removed", part, terminal_identifier, signal),
+        ),
+        PartChange::PinRemapped {
+            part,
+            terminal_identifier,
+            old_signal,
+            new_signal,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}: pin {}: {} -> {}",
+                part, terminal_identifier, old_signal, new_signal
+            ),
+        ),
+        PartChange::PinTypeChanged {
+            part,
+            terminal_identifier,
+            old,
+            new,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}: pin {} type: {} -> {}",
+                part, terminal_identifier, old, new
+            ),
+        ),
+        PartChange::DatasheetChanged { part, old, new } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}: datasheet: {} -> {}",
+                part,
+                describe_value(old),
+                describe_value(new)
+            ),
+        ),
+        PartChange::MetadataChanged {
+            part,
+            key,
+            old,
+            new,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}: {}: {} -> {}",
+                part,
+                key,
+                describe_value(old),
+                describe_value(new)
+            ),
+        ),
+    }
+}
+
+fn describe_component_change(change: &ComponentChange) -> ChangeLine {
+    use ChangeKind::*;
+
+    match change {
+        ComponentChange::Added { component, part } => {
+            ChangeLine::new(Added, format!("{} ({})", component, part))
+        }
+        ComponentChange::Removed { component, part } => {
+            ChangeLine::new(Removed, format!("{} ({})", component, part))
+        }
+        ComponentChange::PartSwapped {
+            component,
+            old_part,
+            new_part,
+        } => ChangeLine::new(
+            Changed,
+            format!("{}: part: {} -> {}", component, old_part, new_part),
+        ),
+        ComponentChange::MetadataChanged {
+            component,
+            key,
+            old,
+            new,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}: {}: {} -> {}",
+                component,
+                key,
+                describe_value(old),
+                describe_value(new)
+            ),
+        ),
+        ComponentChange::MovedNet {
+            component,
+            terminal_identifier,
+            old_net,
+            new_net,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{}.{}: net: {} -> {}",
+                component,
+                terminal_identifier,
+                old_net.as_deref().unwrap_or("(unconnected)"),
+                new_net.as_deref().unwrap_or("(unconnected)")
+            ),
+        ),
+    }
+}
+
+fn describe_net_change(change: &NetChange) -> ChangeLine {
+    use ChangeKind::*;
+
+    match change {
+        NetChange::Added { net, connections } => ChangeLine::new(
+            Added,
+            format!("{} ({})", net, join_connections(connections)),
+        ),
+        NetChange::Removed { net, connections } => ChangeLine::new(
+            Removed,
+            format!("{} ({})", net, join_connections(connections)),
+        ),
+        NetChange::Connected { net, connection } => {
+            ChangeLine::new(Changed, format!("{}: + {}", net, connection))
+        }
+        NetChange::Disconnected { net, connection } => {
+            ChangeLine::new(Changed, format!("{}: - {}", net, connection))
+        }
+        NetChange::Renamed {
+            old_net,
+            new_net,
+            similarity,
+        } => ChangeLine::new(
+            Changed,
+            format!(
+                "{} -> {} (renamed, {:.0}% of pins shared)",
+                old_net,
+                new_net,
+                similarity * 100.0
+            ),
+        ),
+        NetChange::NetTypeChanged { net, old, new } => ChangeLine::new(
+            Changed,
+            format!("{}: type: {} -> {}", net, old.as_str(), new.as_str()),
+        ),
+    }
+}
+
+/// Groups changes by the schematic sheet they apply to, using the sheet name
+/// recorded on each component.
+struct SheetIndex<'a> {
+    left: &'a Schematic,
+    right: &'a Schematic,
 }
 
-pub fn run(_args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
+impl<'a> SheetIndex<'a> {
+    const ROOT: &'static str = "Root sheet";
+    const CROSS_SHEET: &'static str = "Cross-sheet nets";
+
+    fn component_sheet(&self, component: &str) -> String {
+        self.right
+            .get_component(component)
+            .or_else(|| self.left.get_component(component))
+            .and_then(|c| c.as_deref().sheet_name().map(|s| s.to_string()))
+            .unwrap_or(Self::ROOT.to_string())
+    }
+
+    /// Nets are attributed to the sheet of the components they connect. Nets
+    /// which span several sheets are grouped together.
+    fn net_sheet(&self, net: &str) -> String {
+        let net = self.right.get_net(net).or_else(|| self.left.get_net(net));
+        let sheets: BTreeSet<String> = net
+            .iter()
+            .flat_map(|n| {
+                n.as_deref()
+                    .connections
+                    .iter()
+                    .map(|(c, _)| self.component_sheet(&c.as_deref().name))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        match sheets.len() {
+            0 => Self::ROOT.to_string(),
+            1 => sheets.into_iter().next().unwrap(),
+            _ => Self::CROSS_SHEET.to_string(),
+        }
+    }
+
+    fn component_change_sheet(&self, change: &ComponentChange) -> String {
+        match change {
+            ComponentChange::Added { component, .. }
+            | ComponentChange::Removed { component, .. }
+            | ComponentChange::PartSwapped { component, .. }
+            | ComponentChange::MetadataChanged { component, .. }
+            | ComponentChange::MovedNet { component, .. } => self.component_sheet(component),
+        }
+    }
+
+    fn net_change_sheet(&self, change: &NetChange) -> String {
+        match change {
+            NetChange::Added { net, .. }
+            | NetChange::Removed { net, .. }
+            | NetChange::Connected { net, .. }
+            | NetChange::Disconnected { net, .. }
+            | NetChange::NetTypeChanged { net, .. } => self.net_sheet(net),
+            NetChange::Renamed { new_net, .. } => self.net_sheet(new_net),
+        }
+    }
+}
+
+fn print_text(diff: &SchematicDiff) {
+    let print_section = |title: &str, lines: Vec<ChangeLine>| {
+        if lines.is_empty() {
+            return;
+        }
+
+        println!("{}", title.bold());
+        for line in lines.iter() {
+            let line_str = match line.kind {
+                ChangeKind::Added => format!("+ {}", line.description).green(),
+                ChangeKind::Removed => format!("- {}", line.description).red(),
+                ChangeKind::Changed => format!("~ {}", line.description).yellow(),
+            };
+            println!("  {}", line_str);
+        }
+        println!();
+    };
+
+    if diff.is_empty() {
+        println!("{}", "No differences found.".green());
+        return;
+    }
+
+    print_section(
+        "Parts",
+        diff.parts.iter().map(describe_part_change).collect(),
+    );
+    print_section(
+        "Components",
+        diff.components
+            .iter()
+            .map(describe_component_change)
+            .collect(),
+    );
+    print_section("Nets", diff.nets.iter().map(describe_net_change).collect());
+}
+
+fn print_markdown(diff: &SchematicDiff, sheets: &SheetIndex) {
+    // Changes are rendered as `diff` code blocks, so that GitHub colors
+    // additions and removals.
+    let diff_block = |lines: &[ChangeLine]| {
+        let mut block = "```diff\n".to_string();
+        for line in lines.iter() {
+            let prefix = match line.kind {
+                ChangeKind::Added => "+",
+                ChangeKind::Removed => "-",
+                ChangeKind::Changed => "!",
+            };
+            block.push_str(&format!("{} {}\n", prefix, line.description));
+        }
+        block.push_str("```\n");
+        block
+    };
+
+    println!("## Schematic changes\n");
+
+    if diff.is_empty() {
+        println!("No differences found.");
+        return;
+    }
+
+    println!(
+        "{} part, {} component and {} net changes.\n",
+        diff.parts.len(),
+        diff.components.len(),
+        diff.nets.len()
+    );
+
+    if !diff.parts.is_empty() {
+        let lines: Vec<_> = diff.parts.iter().map(describe_part_change).collect();
+        println!("### Library parts\n");
+        println!("{}", diff_block(&lines));
+    }
+
+    let mut by_sheet: HashMap<String, (Vec<ChangeLine>, Vec<ChangeLine>)> = HashMap::new();
+    for change in diff.components.iter() {
+        by_sheet
+            .entry(sheets.component_change_sheet(change))
+            .or_default()
+            .0
+            .push(describe_component_change(change));
+    }
+    for change in diff.nets.iter() {
+        by_sheet
+            .entry(sheets.net_change_sheet(change))
+            .or_default()
+            .1
+            .push(describe_net_change(change));
+    }
+
+    // Sheets in natural order, with the root first and cross-sheet nets last.
+    let mut sheet_names: Vec<&String> = by_sheet.keys().collect();
+    sheet_names.sort_by(|a, b| {
+        let rank = |s: &str| match s {
+            SheetIndex::ROOT => 0,
+            SheetIndex::CROSS_SHEET => 2,
+            _ => 1,
+        };
+        rank(a).cmp(&rank(b)).then_with(|| compare(a, b))
+    });
+
+    for sheet_name in sheet_names {
+        let (components, nets) = &by_sheet[sheet_name];
+        println!("### {}\n", sheet_name);
+        if !components.is_empty() {
+            println!("**Components**\n");
+            println!("{}", diff_block(components));
+        }
+        if !nets.is_empty() {
+            println!("**Nets**\n");
+            println!("{}", diff_block(nets));
+        }
+    }
+}
+
+/// Escape data for a GitHub Actions workflow command.
+fn escape_workflow_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_workflow_property(property: &str) -> String {
+    escape_workflow_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn print_github(diff: &SchematicDiff, file: &Path) {
+    let sections: [(&str, Vec<ChangeLine>); 3] = [
+        (
+            "Part",
+            diff.parts.iter().map(describe_part_change).collect(),
+        ),
+        (
+            "Component",
+            diff.components
+                .iter()
+                .map(describe_component_change)
+                .collect(),
+        ),
+        ("Net", diff.nets.iter().map(describe_net_change).collect()),
+    ];
+
+    for (title, lines) in sections.iter() {
+        for line in lines.iter() {
+            let verb = match line.kind {
+                ChangeKind::Added => "added",
+                ChangeKind::Removed => "removed",
+                ChangeKind::Changed => "changed",
+            };
+            println!(
+                "::notice file={},title={}::{}",
+                escape_workflow_property(&file.display().to_string()),
+                escape_workflow_property(&format!("{} {}", title, verb)),
+                escape_workflow_data(&line.description)
+            );
+        }
+    }
+}
+
+pub fn run(args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let left = load_schematic(&args.left)?;
+    let right = load_schematic(&args.right)?;
+
+    let options = DiffOptions {
+        net_rename_threshold: (!args.no_renames).then_some(args.rename_threshold),
+    };
+    let diff = left.diff_with_options(&right, &options)?;
+
+    match args.format {
+        DiffFormat::Text => print_text(&diff),
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        DiffFormat::Markdown => print_markdown(
+            &diff,
+            &SheetIndex {
+                left: &left,
+                right: &right,
+            },
+        ),
+        DiffFormat::Github => print_github(&diff, &args.right),
+    }
+
     Ok(())
 }
@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use colored::Colorize;
+use schematics2kicad::kicad_netlist_from_schematics;
+use schematics2spice::{spice_netlist_from_schematics, SpiceModel, SpiceOptions};
+
+use crate::load::{load_schematic, split_sheet_path, NetTypeArgs};
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The canonical JSON representation of the schematic.
+    Json,
+    /// A KiCad netlist (.net), which can be imported into pcbnew.
+    Kicad,
+    /// A SPICE deck (.cir), to simulate the circuit.
+    Spice,
+}
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    #[clap(
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module to export"
+    )]
+    input: PathBuf,
+
+    #[clap(long, value_enum, default_value_t = ExportFormat::Json, help = "Output format")]
+    format: ExportFormat,
+
+    #[clap(
+        short,
+        long,
+        help = "File to write the export to, instead of standard output"
+    )]
+    output: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Only export the components on this sheet and the sheets below it, e.g. /Power/LDO/ (spice only)"
+    )]
+    sheet: Option<String>,
+
+    #[clap(
+        long,
+        help = "Directory of SPICE models to use for components, each file named after an MPN, e.g. AP2112K-3.3TRG1.lib (spice only)"
+    )]
+    models: Option<PathBuf>,
+
+    #[clap(flatten)]
+    net_types: NetTypeArgs,
+}
+
+/// Load the models in `dir`, keyed by the names of their files without the
+/// extension. Files without a `.subckt` are skipped with a warning.
+fn load_models(dir: &Path) -> Result<HashMap<String, SpiceModel>, Box<dyn std::error::Error>> {
+    let mut models = HashMap::new();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read \"{}\": {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        let Some(mpn) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !path.is_file() {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+        match SpiceModel::parse(path.clone(), &contents) {
+            Some(model) => {
+                models.insert(mpn.to_string(), model);
+            }
+            None => eprintln!(
+                "{} skipping \"{}\", it has no .subckt",
+                "warning:".yellow().bold(),
+                path.display()
+            ),
+        }
+    }
+    Ok(models)
+}
+
+impl ExportArgs {
+    /// Reject the options that only apply to SPICE decks when exporting
+    /// another format, rather than ignoring them.
+    fn validate(&self) -> Result<(), String> {
+        if self.format == ExportFormat::Spice {
+            return Ok(());
+        }
+        for (flag, given) in [
+            ("--sheet", self.sheet.is_some()),
+            ("--models", self.models.is_some()),
+        ] {
+            if given {
+                return Err(format!("{} can only be used with --format spice", flag));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn run(args: ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    args.validate()?;
+    let mut schematic = load_schematic(&args.input)?;
+    args.net_types.apply(&mut schematic)?;
+
+    let contents = match args.format {
+        ExportFormat::Json => schematic.to_json()? + "\n",
+        ExportFormat::Kicad => kicad_netlist_from_schematics(&schematic),
+        ExportFormat::Spice => {
+            let options = SpiceOptions {
+                title: args.input.display().to_string(),
+                sheet: args.sheet.as_deref().map(split_sheet_path),
+                models: match args.models.as_ref() {
+                    Some(dir) => load_models(dir)?,
+                    None => HashMap::new(),
+                },
+            };
+            spice_netlist_from_schematics(&schematic, &options)
+        }
+    };
+
+    match args.output {
+        Some(output) => std::fs::write(&output, contents)
+            .map_err(|e| format!("Failed to write \"{}\": {}", output.display(), e))?,
+        None => print!("{}", contents),
+    }
+
+    Ok(())
+}
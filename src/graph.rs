@@ -0,0 +1,91 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use schematics::graph::GraphFilter;
+
+use crate::load::{load_schematic, split_sheet_path, NetTypeArgs};
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz's DOT language.
+    Dot,
+    /// An SVG image, rendered with Graphviz's `dot`.
+    Svg,
+}
+
+#[derive(clap::Args)]
+pub struct GraphArgs {
+    #[clap(
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module to draw"
+    )]
+    input: PathBuf,
+
+    #[clap(long, value_enum, default_value_t = GraphFormat::Dot, help = "Output format")]
+    format: GraphFormat,
+
+    #[clap(
+        short,
+        long,
+        help = "File to write the graph to, instead of standard output"
+    )]
+    output: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Only draw the components on this sheet and the sheets below it, e.g. /Power/LDO/"
+    )]
+    sheet: Option<String>,
+
+    #[clap(
+        long,
+        help = "Only draw the components on this net, and the nets they're connected to"
+    )]
+    net: Option<String>,
+
+    #[clap(flatten)]
+    net_types: NetTypeArgs,
+}
+
+/// Render DOT as an SVG image with Graphviz.
+fn render_svg(dot: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run `dot`, install Graphviz to render SVG: {}", e))?;
+    child.stdin.take().unwrap().write_all(dot.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!("Failed to render the graph: {}", output.status).into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+pub fn run(args: GraphArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut schematic = load_schematic(&args.input)?;
+    args.net_types.apply(&mut schematic)?;
+
+    let filter = GraphFilter {
+        sheet: args.sheet.as_deref().map(split_sheet_path),
+        net: args.net.clone(),
+    };
+    let dot = schematic.to_dot(&filter)?;
+
+    let contents = match args.format {
+        GraphFormat::Dot => dot,
+        GraphFormat::Svg => render_svg(&dot)?,
+    };
+
+    match args.output {
+        Some(output) => std::fs::write(&output, contents)
+            .map_err(|e| format!("Failed to write \"{}\": {}", output.display(), e))?,
+        None => print!("{}", contents),
+    }
+
+    Ok(())
+}
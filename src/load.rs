@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use atopile::{schematics_from_atopile_module, schematics_from_atopile_project};
+use colored::*;
+use kicad2schematics::{schematics_from_kicad_netlist, schematics_from_kicad_schematic};
+use schematics::{net_inference::NetTypeOverrides, Schematic};
+
+/// Load a Schematic from a KiCad netlist (`.net`), a root schematic
+/// (`.kicad_sch`), or a project (`.kicad_pro`), in which case the root
+/// schematic of the project is loaded. Atopile sources can be loaded either
+/// as a project directory (containing `ato.yaml`) or as `file.ato:Module`.
+/// Schematics previously exported as JSON can be loaded too.
+pub fn load_schematic(path: &Path) -> Result<Schematic, Box<dyn std::error::Error>> {
+    if path.is_dir() {
+        return Ok(schematics_from_atopile_project(path)?);
+    }
+
+    if let Some((file, module)) = path.to_str().and_then(|p| p.rsplit_once(':')) {
+        if file.ends_with(".ato") {
+            return Ok(schematics_from_atopile_module(Path::new(file), module)?);
+        }
+    }
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("kicad_sch") => Ok(schematics_from_kicad_schematic(path)?),
+        Some("json") => {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+            Ok(Schematic::from_json(&json)?)
+        }
+        Some("kicad_pro") => Ok(schematics_from_kicad_schematic(
+            &path.with_extension("kicad_sch"),
+        )?),
+        _ => {
+            let netlist = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+            Ok(schematics_from_kicad_netlist(&netlist)?)
+        }
+    }
+}
+
+/// Split a sheet path given on the command line, e.g. `/Power/LDO/`, into the
+/// names of its sheets.
+pub fn split_sheet_path(sheet: &str) -> Vec<String> {
+    sheet
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Options controlling how the types of nets (power, ground, ...) are
+/// decided when loading a design.
+#[derive(clap::Args)]
+pub struct NetTypeArgs {
+    #[clap(
+        long,
+        help = "JSON file setting the type of nets by name, e.g. {\"nets\": {\"VMOTOR\": \"power\"}}"
+    )]
+    net_types: Option<PathBuf>,
+
+    #[clap(long, help = "Print how the type of each net was decided")]
+    net_type_report: bool,
+}
+
+impl NetTypeArgs {
+    /// Re-infer the net types of `schematic` with the user's overrides, and
+    /// print the report if asked to. The report goes to standard error, so
+    /// that it doesn't mix with exported output.
+    pub fn apply(&self, schematic: &mut Schematic) -> Result<(), Box<dyn std::error::Error>> {
+        if self.net_types.is_none() && !self.net_type_report {
+            return Ok(());
+        }
+
+        let overrides = match self.net_types.as_ref() {
+            Some(path) => {
+                let overrides = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+                serde_json::from_str(&overrides)
+                    .map_err(|e| format!("Invalid net types \"{}\": {}", path.display(), e))?
+            }
+            None => NetTypeOverrides::default(),
+        };
+
+        let report = schematic.infer_net_types(&overrides)?;
+        if self.net_type_report {
+            for decision in report.nets.iter() {
+                eprintln!(
+                    "{}: {} ({})",
+                    decision.net.bold(),
+                    decision.net_type.as_str(),
+                    decision.reason
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The command line flags that reproduce these options.
+    pub fn command_args(&self) -> String {
+        let mut args = String::new();
+        if let Some(net_types) = self.net_types.as_ref() {
+            args.push_str(&format!("--net-types \"{}\" ", net_types.display()));
+        }
+        if self.net_type_report {
+            args.push_str("--net-type-report ");
+        }
+        args
+    }
+}
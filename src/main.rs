@@ -1,16 +1,34 @@
+use std::process::ExitCode;
+
+use bom::BomArgs;
+use check::CheckArgs;
 use clap::Parser;
-use convert::ConvertArgs;
-// use diff::DiffArgs;
+use convert::{ConvertArgs, ConvertError};
+use diff::DiffArgs;
+use export::ExportArgs;
+use graph::GraphArgs;
 use inquire::ui::{Color, RenderConfig, StyleSheet, Styled};
+use query::QueryArgs;
 
+mod bom;
+mod check;
 mod convert;
-// mod diff;
+mod diff;
+mod export;
+mod graph;
+mod load;
+mod query;
 
 #[derive(Parser)]
 #[command(version, about, name = "diode", bin_name = "diode")]
 enum DiodeCli {
+    Bom(BomArgs),
+    Check(CheckArgs),
     Convert(ConvertArgs),
-    // Diff(DiffArgs),
+    Diff(DiffArgs),
+    Export(ExportArgs),
+    Graph(GraphArgs),
+    Query(QueryArgs),
 }
 
 fn get_inquire_config() -> RenderConfig<'static> {
@@ -21,13 +39,35 @@ fn get_inquire_config() -> RenderConfig<'static> {
     config
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// The exit code for a failed command: the code of the error if it has one of
+/// its own, or else 1.
+fn exit_code(error: &(dyn std::error::Error + 'static)) -> u8 {
+    match error.downcast_ref::<ConvertError>() {
+        Some(error) => error.exit_code(),
+        None => 1,
+    }
+}
+
+fn main() -> ExitCode {
     inquire::set_global_render_config(get_inquire_config());
 
     let args = DiodeCli::parse();
 
-    match args {
+    let result = match args {
+        DiodeCli::Bom(args) => bom::run(args),
+        DiodeCli::Check(args) => check::run(args),
         DiodeCli::Convert(args) => convert::run(args),
-        // DiodeCli::Diff(args) => diff::run(args),
+        DiodeCli::Diff(args) => diff::run(args),
+        DiodeCli::Export(args) => export::run(args),
+        DiodeCli::Graph(args) => graph::run(args),
+        DiodeCli::Query(args) => query::run(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            ExitCode::from(exit_code(error.as_ref()))
+        }
     }
 }
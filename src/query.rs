@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use colored::*;
+use schematics::query::{Query, QueryResult};
+
+use crate::load::load_schematic;
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFormat {
+    /// A table with a column per field.
+    Table,
+    /// The results as a JSON array of objects, for scripts.
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct QueryArgs {
+    #[clap(
+        help = "Path to the KiCad netlist (.net), schematic (.kicad_sch), project (.kicad_pro), Atopile project directory or file.ato:Module to query"
+    )]
+    input: PathBuf,
+
+    #[clap(
+        help = "The query, e.g. 'nets where connects(U1.SDA)' or 'components where part ~ \"STM32*\"'"
+    )]
+    query: String,
+
+    #[clap(long, value_enum, default_value_t = QueryFormat::Table, help = "Output format")]
+    format: QueryFormat,
+}
+
+fn print_table(result: &QueryResult) {
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            result
+                .rows
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", line(&result.columns).bold());
+    for row in result.rows.iter() {
+        println!("{}", line(row));
+    }
+    println!();
+    println!("{} result(s)", result.rows.len());
+}
+
+pub fn run(args: QueryArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let query = Query::parse(&args.query)?;
+    let schematic = load_schematic(&args.input)?;
+    let result = schematic.query(&query)?;
+
+    match args.format {
+        QueryFormat::Table => print_table(&result),
+        QueryFormat::Json => println!("{}", serde_json::to_string_pretty(&result.records())?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::process::{Command, Output};
+
+fn diode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_diode"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_diff_rename_threshold() {
+    for threshold in ["1.5", "-0.1", "NaN", "half"] {
+        let flag = format!("--rename-threshold={}", threshold);
+        let output = diode(&["diff", "a.net", "b.net", &flag]);
+        assert_eq!(output.status.code(), Some(2), "threshold {}", threshold);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--rename-threshold"), "{}", stderr);
+    }
+
+    // A valid threshold gets as far as loading the inputs.
+    let output = diode(&["diff", "missing.net", "b.net", "--rename-threshold", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+const LEFT: &str = "tests/fixtures/diff/left.json";
+const RIGHT: &str = "tests/fixtures/diff/right.json";
+
+#[test]
+fn test_diff_text() {
+    let output = diode(&["diff", LEFT, RIGHT]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "Parts",
+            "  - C",
+            "  ~ LED: pin 2: A -> ANODE",
+            "  + SW_Push",
+            "",
+            "Components",
+            "  - C1 (C)",
+            "  ~ R1: Value: \"1k\" -> \"2k2\"",
+            "  + SW1 (SW_Push)",
+            "",
+            "Nets",
+            "  + /BTN (SW1.2)",
+            "  - /FILT (C1.1)",
+            "  ~ GND: - C1.2",
+            "  ~ VCC: + SW1.1",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_json() {
+    let output = diode(&["diff", LEFT, RIGHT, "--format", "json"]);
+    assert!(output.status.success());
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let changes = |section: &str, key: &str| -> Vec<(String, String)> {
+        diff[section]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                (
+                    c["change"].as_str().unwrap().to_string(),
+                    c[key].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    };
+    let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    };
+
+    assert_eq!(
+        changes("parts", "part"),
+        pairs(&[("removed", "C"), ("pin_remapped", "LED"), ("added", "SW_Push")])
+    );
+    assert_eq!(
+        changes("components", "component"),
+        pairs(&[
+            ("removed", "C1"),
+            ("metadata_changed", "R1"),
+            ("added", "SW1")
+        ])
+    );
+    assert_eq!(
+        changes("nets", "net"),
+        pairs(&[
+            ("added", "/BTN"),
+            ("removed", "/FILT"),
+            ("disconnected", "GND"),
+            ("connected", "VCC")
+        ])
+    );
+    assert_eq!(diff["nets"][2]["connection"]["component"], "C1");
+    assert_eq!(diff["nets"][3]["connection"]["component"], "SW1");
+}
+
+#[test]
+fn test_diff_identical() {
+    let output = diode(&["diff", LEFT, LEFT]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "No differences found.\n"
+    );
+}